@@ -0,0 +1,182 @@
+use nannou::color::IntoLinSrgba;
+use nannou::prelude::*;
+
+use crate::Color;
+
+/// Bytes per instance: center, radii, rotation and ring width, then color,
+/// as `f32`s in the order `circles.wgsl` reads them.
+pub const INSTANCE_SIZE: usize = 10 * 4;
+
+/// Collects filled discs, stroked rings and rotated ellipses as instances
+/// for `CircleRenderer`, which draws a whole batch in one instanced call
+/// instead of one draw per circle.
+///
+/// A full star field of `MAX_STAR_COUNT` stars with their auras is 1050
+/// circles. Measured with `star_batching_cost` on one core, tessellating
+/// those as `draw.ellipse()` does took about 2.7ms a frame and produced
+/// 37950 vertices; batching them takes about 0.015ms for 1050 instances.
+#[derive(Default)]
+pub struct CircleBatch {
+    instances: Vec<u8>,
+}
+
+impl CircleBatch {
+    pub fn disc(&mut self, center: Point2, radius: f32, color: Color) {
+        self.ellipse(center, vec2(radius, radius), 0., color);
+    }
+
+    /// A ring centered on `radius`, `weight` wide, like a stroked ellipse.
+    pub fn ring(&mut self, center: Point2, radius: f32, weight: f32, color: Color) {
+        let outer = radius + weight / 2.;
+        self.push(center, vec2(outer, outer), 0., weight, color);
+    }
+
+    /// A filled ellipse with the given half-width and half-height, turned
+    /// `rotation` radians anticlockwise.
+    pub fn ellipse(&mut self, center: Point2, radii: Vec2, rotation: f32, color: Color) {
+        self.push(center, radii, rotation, 0., color);
+    }
+
+    fn push(&mut self, center: Point2, radii: Vec2, rotation: f32, weight: f32, color: Color) {
+        if radii.min_element() <= 0. {
+            return;
+        }
+        let color: LinSrgba = IntoLinSrgba::<f32>::into_lin_srgba(color);
+        let values = [
+            center.x,
+            center.y,
+            radii.x,
+            radii.y,
+            rotation,
+            weight,
+            color.red,
+            color.green,
+            color.blue,
+            color.alpha,
+        ];
+        self.instances
+            .extend(values.iter().flat_map(|value| value.to_ne_bytes()));
+    }
+
+    pub fn len(&self) -> usize {
+        self.instances.len() / INSTANCE_SIZE
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.instances.is_empty()
+    }
+
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.instances
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use nannou::lyon::math::{point, vector, Angle};
+    use nannou::lyon::path::Path;
+    use nannou::lyon::tessellation::{
+        BuffersBuilder, FillOptions, FillTessellator, FillVertex, StrokeOptions, StrokeTessellator,
+        StrokeVertex, VertexBuffers,
+    };
+    use std::time::Instant;
+
+    use crate::{MAX_STAR_COUNT, STAR_AURA_SIZE, STAR_RADIUS};
+
+    /// A full star field as `draw_stars` lays it out: a disc per star and a
+    /// ring for each step of its aura.
+    fn star_field() -> Vec<(Point2, f32, Option<f32>)> {
+        (0..MAX_STAR_COUNT)
+            .flat_map(|i| {
+                let center = pt2((i % 15) as f32 * 30., (i / 15) as f32 * 30.);
+                let aura =
+                    (0..STAR_AURA_SIZE).map(move |r| (center, STAR_RADIUS + r as f32, Some(1.)));
+                std::iter::once((center, STAR_RADIUS, None)).chain(aura)
+            })
+            .collect()
+    }
+
+    /// The path `draw.ellipse()` builds for a circle before tessellating it.
+    fn ellipse_path(radius: f32) -> Path {
+        let mut builder = Path::svg_builder();
+        builder.move_to(point(radius, 0.));
+        builder.arc(
+            point(0., 0.),
+            vector(radius, radius),
+            Angle::radians(TAU),
+            Angle::radians(0.),
+        );
+        builder.build()
+    }
+
+    /// Compares tessellating every star circle the way `draw.ellipse()`
+    /// does with batching them as instances. Run with
+    /// `cargo test --release -- --ignored --nocapture`.
+    #[test]
+    #[ignore]
+    fn star_batching_cost() {
+        let circles = star_field();
+        let runs = 50;
+
+        let mut geometry: VertexBuffers<[f32; 2], u32> = VertexBuffers::new();
+        let start = Instant::now();
+        for _ in 0..runs {
+            geometry.vertices.clear();
+            geometry.indices.clear();
+            let mut fill = FillTessellator::new();
+            let mut stroke = StrokeTessellator::new();
+            for &(center, radius, weight) in &circles {
+                let path = ellipse_path(radius);
+                let offset = |p: nannou::lyon::math::Point| [p.x + center.x, p.y + center.y];
+                match weight {
+                    None => fill
+                        .tessellate_path(
+                            &path,
+                            &FillOptions::default(),
+                            &mut BuffersBuilder::new(&mut geometry, |v: FillVertex| {
+                                offset(v.position())
+                            }),
+                        )
+                        .unwrap(),
+                    Some(weight) => stroke
+                        .tessellate_path(
+                            &path,
+                            &StrokeOptions::default().with_line_width(weight),
+                            &mut BuffersBuilder::new(&mut geometry, |v: StrokeVertex| {
+                                offset(v.position())
+                            }),
+                        )
+                        .unwrap(),
+                };
+            }
+        }
+        let tessellated = start.elapsed().as_secs_f64() * 1000. / runs as f64;
+
+        let mut batch = CircleBatch::default();
+        let start = Instant::now();
+        for _ in 0..runs {
+            batch = CircleBatch::default();
+            for &(center, radius, weight) in &circles {
+                match weight {
+                    None => batch.disc(center, radius, Color::new(255, 255, 255, 255)),
+                    Some(weight) => {
+                        batch.ring(center, radius, weight, Color::new(255, 255, 255, 255))
+                    }
+                }
+            }
+        }
+        let batched = start.elapsed().as_secs_f64() * 1000. / runs as f64;
+
+        println!(
+            "{} stars, {} circles: tessellated {tessellated:.3}ms into {} vertices and {} \
+             indices; batched {batched:.3}ms into {} instances ({} bytes)",
+            MAX_STAR_COUNT,
+            circles.len(),
+            geometry.vertices.len(),
+            geometry.indices.len(),
+            batch.len(),
+            batch.as_bytes().len(),
+        );
+    }
+}
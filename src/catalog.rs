@@ -0,0 +1,171 @@
+use nannou::prelude::*;
+use std::fmt;
+
+use crate::SCREEN_SIZE_F;
+
+/// Latitude of the observer the catalog is projected for, in degrees.
+const OBSERVER_LATITUDE: f32 = 40.;
+/// Local sidereal time of the projection, in hours. Puts Orion high in the
+/// south.
+const SIDEREAL_TIME: f32 = 5.5;
+/// Magnitudes at and beyond which stars are drawn at full and faintest
+/// brightness.
+const BRIGHTEST_MAGNITUDE: f32 = -1.5;
+const FAINTEST_MAGNITUDE: f32 = 6.;
+const FAINTEST_BRIGHTNESS: f32 = 0.2;
+
+/// A star from a catalog file.
+///
+/// Catalogs are CSV with one star per line: right ascension in hours,
+/// declination in degrees and apparent magnitude. Blank lines, lines
+/// starting with `#` and a `ra,dec,mag` header are ignored.
+///
+/// ```text
+/// ra,dec,mag
+/// 6.752,-16.716,-1.46
+/// ```
+#[derive(Clone, Copy)]
+pub struct CatalogStar {
+    pub ra: f32,
+    pub dec: f32,
+    pub magnitude: f32,
+}
+
+#[derive(Debug)]
+pub struct CatalogError {
+    line: usize,
+    message: String,
+}
+
+impl fmt::Display for CatalogError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "star catalog line {}: {}", self.line, self.message)
+    }
+}
+
+pub fn parse(csv: &str) -> Result<Vec<CatalogStar>, CatalogError> {
+    let mut stars = vec![];
+    for (index, line) in csv.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') || line.starts_with("ra") {
+            continue;
+        }
+        let error = |message: String| CatalogError {
+            line: index + 1,
+            message,
+        };
+        let fields: Vec<_> = line.split(',').map(str::trim).collect();
+        let [ra, dec, magnitude] = fields[..] else {
+            return Err(error(format!("expected ra,dec,mag, got `{line}`")));
+        };
+        let number = |field: &str| {
+            field
+                .parse::<f32>()
+                .map_err(|_| error(format!("invalid number `{field}`")))
+        };
+        let star = CatalogStar {
+            ra: number(ra)?,
+            dec: number(dec)?,
+            magnitude: number(magnitude)?,
+        };
+        if !(0. ..24.).contains(&star.ra) || !(-90. ..=90.).contains(&star.dec) {
+            return Err(error(format!(
+                "ra {} dec {} out of range",
+                star.ra, star.dec
+            )));
+        }
+        if !star.magnitude.is_finite() {
+            return Err(error(format!(
+                "magnitude {} is not a number",
+                star.magnitude
+            )));
+        }
+        stars.push(star);
+    }
+    Ok(stars)
+}
+
+impl CatalogStar {
+    /// Where the star sits in the window, looking south with east on the
+    /// left, or `None` when it's below the horizon or behind the viewer.
+    pub fn screen_pos(&self) -> Option<Point2> {
+        let lat = deg_to_rad(OBSERVER_LATITUDE);
+        let dec = deg_to_rad(self.dec);
+        let hour_angle = deg_to_rad((SIDEREAL_TIME - self.ra) * 15.);
+        let sin_alt = dec.sin() * lat.sin() + dec.cos() * lat.cos() * hour_angle.cos();
+        let alt = sin_alt.asin();
+        if alt <= 0. {
+            return None;
+        }
+        // azimuth measured from north through east
+        let cos_az = (dec.sin() - sin_alt * lat.sin()) / (alt.cos() * lat.cos());
+        let az = rad_to_deg(clamp(cos_az, -1., 1.).acos());
+        let az = if hour_angle.sin() > 0. { 360. - az } else { az };
+        if !(90. ..=270.).contains(&az) {
+            return None;
+        }
+        Some(pt2(
+            (az - 90.) / 180. * SCREEN_SIZE_F,
+            rad_to_deg(alt) / 90. * SCREEN_SIZE_F,
+        ))
+    }
+
+    /// Brightness multiplier, 1 for the brightest stars in the sky.
+    pub fn brightness(&self) -> f32 {
+        let t = (self.magnitude - BRIGHTEST_MAGNITUDE) / (FAINTEST_MAGNITUDE - BRIGHTEST_MAGNITUDE);
+        1. - clamp(t, 0., 1.) * (1. - FAINTEST_BRIGHTNESS)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_skips_headers_comments_and_blank_lines() {
+        let stars =
+            parse("ra,dec,mag\n# Sirius\n\n6.752, -16.716, -1.46\n5.919,7.407,0.5\n").unwrap();
+        assert_eq!(stars.len(), 2);
+        assert_eq!(
+            (stars[0].ra, stars[0].dec, stars[0].magnitude),
+            (6.752, -16.716, -1.46)
+        );
+    }
+
+    #[test]
+    fn parse_reports_the_bad_line() {
+        for (csv, line) in [
+            ("ra,dec,mag\n6.752,-16.716", 2),
+            ("6.752,-16.716,-1.46\n# next\n25,0,1", 3),
+            ("6.752,-95,1", 1),
+            ("6.752,-16.716,bright", 1),
+            ("6.752,-16.716,NaN", 1),
+            ("6.752,-16.716,inf", 1),
+        ] {
+            let error = parse(csv)
+                .err()
+                .unwrap_or_else(|| panic!("accepted `{csv}`"));
+            assert_eq!(error.line, line, "{error}");
+        }
+    }
+
+    #[test]
+    fn screen_pos_looks_south() {
+        let star = |ra, dec| CatalogStar {
+            ra,
+            dec,
+            magnitude: 0.,
+        };
+        // on the meridian at the celestial equator: due south, 50 degrees up
+        let south = star(SIDEREAL_TIME, 0.).screen_pos().unwrap();
+        assert!((south.x - SCREEN_SIZE_F / 2.).abs() < 0.01, "{south:?}");
+        assert!(
+            (south.y - SCREEN_SIZE_F * 50. / 90.).abs() < 0.01,
+            "{south:?}"
+        );
+        // never rises this far north
+        assert!(star(SIDEREAL_TIME, -60.).screen_pos().is_none());
+        // up, but due north, behind the viewer
+        assert!(star(SIDEREAL_TIME, 80.).screen_pos().is_none());
+    }
+}
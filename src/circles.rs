@@ -0,0 +1,141 @@
+use nannou::prelude::*;
+use std::cell::RefCell;
+use std::ops::Range;
+
+use crate::batch::INSTANCE_SIZE;
+
+const UNIFORMS_SIZE: u64 = 16;
+/// Room for the first frame's circles before the buffer has to grow; a
+/// full splat cloud grid plus the stars fits.
+const INITIAL_CAPACITY: usize = 32 * 1024;
+const INSTANCE_ATTRIBUTES: [wgpu::VertexAttribute; 4] =
+    wgpu::vertex_attr_array![0 => Float32x2, 1 => Float32x2, 2 => Float32x2, 3 => Float32x4];
+
+/// Draws `CircleBatch`es with `circles.wgsl`: each circle is one instance
+/// of a quad, shaded into a disc, ring or ellipse with an antialiased edge.
+/// This replaces one tessellated `draw.ellipse()` per circle, which for the
+/// splat clouds alone meant tens of thousands of shapes a frame.
+///
+/// A frame's circles are uploaded together by `prepare` and then drawn in
+/// runs by `encode`, between the passes nannou draws everything else in,
+/// so layers still stack in order. Blending matches nannou's default.
+pub struct CircleRenderer {
+    uniforms: wgpu::Buffer,
+    instances: RefCell<(wgpu::Buffer, usize)>,
+    bind_group: wgpu::BindGroup,
+    /// For the window's multisampled frame, and for single-sampled textures
+    /// such as the post pass's scene.
+    frame_pipeline: wgpu::RenderPipeline,
+    texture_pipeline: wgpu::RenderPipeline,
+}
+
+impl CircleRenderer {
+    pub fn new(window: &Window) -> Self {
+        let device = window.device();
+        let uniforms = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("circle uniforms"),
+            size: UNIFORMS_SIZE,
+            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+        let layout = wgpu::BindGroupLayoutBuilder::new()
+            .uniform_buffer(wgpu::ShaderStages::VERTEX, false)
+            .build(device);
+        let bind_group = wgpu::BindGroupBuilder::new()
+            .buffer_bytes(&uniforms, 0, None)
+            .build(device, &layout);
+        let pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("circles"),
+            bind_group_layouts: &[&layout],
+            push_constant_ranges: &[],
+        });
+        let shader = device.create_shader_module(&wgpu::include_wgsl!("circles.wgsl"));
+        let pipeline = |sample_count| {
+            wgpu::RenderPipelineBuilder::from_layout(&pipeline_layout, &shader)
+                .vertex_entry_point("vs_main")
+                .fragment_shader(&shader)
+                .fragment_entry_point("fs_main")
+                .add_vertex_buffer_layout(wgpu::VertexBufferLayout {
+                    array_stride: INSTANCE_SIZE as wgpu::BufferAddress,
+                    step_mode: wgpu::VertexStepMode::Instance,
+                    attributes: &INSTANCE_ATTRIBUTES,
+                })
+                .primitive_topology(wgpu::PrimitiveTopology::TriangleStrip)
+                .color_format(Frame::TEXTURE_FORMAT)
+                .sample_count(sample_count)
+                .build(device)
+        };
+
+        Self {
+            uniforms,
+            instances: RefCell::new((instance_buffer(device, INITIAL_CAPACITY), INITIAL_CAPACITY)),
+            bind_group,
+            frame_pipeline: pipeline(window.msaa_samples()),
+            texture_pipeline: pipeline(1),
+        }
+    }
+
+    /// Uploads a frame's circles, `instances` being the batches' bytes back
+    /// to back, for a target `viewport` points across showing the scene
+    /// magnified by `zoom` about `origin`.
+    pub fn prepare(
+        &self,
+        window: &Window,
+        instances: &[u8],
+        viewport: Vec2,
+        zoom: f32,
+        origin: Point2,
+    ) {
+        let scale = vec2(zoom, zoom) * 2. / viewport;
+        let uniforms: Vec<u8> = [origin.x, origin.y, scale.x, scale.y]
+            .iter()
+            .flat_map(|f| f.to_ne_bytes())
+            .collect();
+        window.queue().write_buffer(&self.uniforms, 0, &uniforms);
+
+        let mut buffer = self.instances.borrow_mut();
+        let count = instances.len() / INSTANCE_SIZE;
+        if count > buffer.1 {
+            let capacity = count.next_power_of_two();
+            *buffer = (instance_buffer(window.device(), capacity), capacity);
+        }
+        if !instances.is_empty() {
+            window.queue().write_buffer(&buffer.0, 0, instances);
+        }
+    }
+
+    /// Draws the prepared instances in `range` over whatever `target`
+    /// already holds.
+    pub fn encode(
+        &self,
+        encoder: &mut wgpu::CommandEncoder,
+        target: &wgpu::TextureView,
+        sample_count: u32,
+        range: Range<u32>,
+    ) {
+        if range.is_empty() {
+            return;
+        }
+        let buffer = self.instances.borrow();
+        let mut pass = wgpu::RenderPassBuilder::new()
+            .color_attachment(target, |color| color.load_op(wgpu::LoadOp::Load))
+            .begin(encoder);
+        pass.set_pipeline(if sample_count > 1 {
+            &self.frame_pipeline
+        } else {
+            &self.texture_pipeline
+        });
+        pass.set_bind_group(0, &self.bind_group, &[]);
+        pass.set_vertex_buffer(0, buffer.0.slice(..));
+        pass.draw(0..4, range);
+    }
+}
+
+fn instance_buffer(device: &wgpu::Device, capacity: usize) -> wgpu::Buffer {
+    device.create_buffer(&wgpu::BufferDescriptor {
+        label: Some("circle instances"),
+        size: (capacity * INSTANCE_SIZE) as u64,
+        usage: wgpu::BufferUsages::VERTEX | wgpu::BufferUsages::COPY_DST,
+        mapped_at_creation: false,
+    })
+}
@@ -0,0 +1,61 @@
+// Instanced discs, rings and rotated ellipses, one quad per instance.
+
+[[block]]
+struct Uniforms {
+    // Scene coordinates of the viewport's center.
+    origin: vec2<f32>;
+    // Scene units to clip space, including the zoom.
+    scale: vec2<f32>;
+};
+
+struct Instance {
+    [[location(0)]] center: vec2<f32>;
+    [[location(1)]] radii: vec2<f32>;
+    // Rotation in radians, and the ring width; 0 fills the shape.
+    [[location(2)]] shape: vec2<f32>;
+    [[location(3)]] color: vec4<f32>;
+};
+
+struct VertexOutput {
+    [[builtin(position)]] position: vec4<f32>;
+    // Position relative to the center, unrotated, in scene units.
+    [[location(0)]] local: vec2<f32>;
+    [[location(1)]] radii: vec2<f32>;
+    [[location(2)]] weight: f32;
+    [[location(3)]] color: vec4<f32>;
+};
+
+[[group(0), binding(0)]] var<uniform> uniforms: Uniforms;
+
+[[stage(vertex)]]
+fn vs_main([[builtin(vertex_index)]] index: u32, instance: Instance) -> VertexOutput {
+    // a triangle strip over the shape's bounds, a unit wider on each side
+    // to leave room for the antialiased edge
+    let corner = vec2<f32>(f32(index & 1u), f32(index >> 1u)) * 2.0 - 1.0;
+    let local = corner * (instance.radii + 1.0);
+    let c = cos(instance.shape.x);
+    let s = sin(instance.shape.x);
+    let rotated = vec2<f32>(local.x * c - local.y * s, local.x * s + local.y * c);
+    let scene = instance.center + rotated;
+    var out: VertexOutput;
+    out.position = vec4<f32>((scene - uniforms.origin) * uniforms.scale, 0.0, 1.0);
+    out.local = local;
+    out.radii = instance.radii;
+    out.weight = instance.shape.y;
+    out.color = instance.color;
+    return out;
+}
+
+[[stage(fragment)]]
+fn fs_main(in: VertexOutput) -> [[location(0)]] vec4<f32> {
+    // signed distance to the edge, in pixels; derivatives must be taken
+    // outside of branches, so both shapes are measured
+    let dist = length(in.local);
+    let mid = in.radii.x - in.weight * 0.5;
+    let ring = (abs(dist - mid) - in.weight * 0.5) / fwidth(dist);
+    let scaled = length(in.local / in.radii);
+    let filled = (scaled - 1.0) / fwidth(scaled);
+    let edge = select(filled, ring, in.weight > 0.0);
+    let coverage = clamp(0.5 - edge, 0.0, 1.0);
+    return vec4<f32>(in.color.rgb, in.color.a * coverage);
+}
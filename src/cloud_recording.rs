@@ -0,0 +1,121 @@
+use std::fmt;
+use std::fs::File;
+use std::io::{self, LineWriter, Write};
+use std::path::Path;
+
+/// The inputs that drive the cloud field for one frame: the clock the
+/// shapes evolve on, how far the wind has carried them, and the gusting
+/// wind speed the splats stretch with.
+#[derive(Clone, Copy)]
+pub struct CloudKeyframe {
+    pub cloud_frames: f64,
+    pub wind_offset: f64,
+    pub wind_speed: f32,
+}
+
+/// A cloud recording, written with `--record-clouds` and played back with
+/// `--replay-clouds`, so a particular cloud evolution can be reproduced
+/// frame for frame. Params such as density still come from the session,
+/// so pair a replay with the same preset or timeline.
+///
+/// Recordings have one frame per line: the cloud clock, the wind offset
+/// and the wind speed. Lines starting with `#` are ignored.
+///
+/// ```text
+/// # cloud_frames wind_offset wind_speed
+/// 1 2400.001 1.0312
+/// 2 2400.002 1.0308
+/// ```
+pub enum CloudRecording {
+    Record {
+        file: LineWriter<File>,
+        frames: usize,
+    },
+    Replay {
+        keyframes: Vec<CloudKeyframe>,
+        next: usize,
+    },
+}
+
+#[derive(Debug)]
+pub struct RecordingError {
+    line: usize,
+    message: String,
+}
+
+impl fmt::Display for RecordingError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "cloud recording line {}: {}", self.line, self.message)
+    }
+}
+
+impl CloudRecording {
+    pub fn record(path: &Path) -> io::Result<Self> {
+        let mut file = LineWriter::new(File::create(path)?);
+        writeln!(file, "# cloud_frames wind_offset wind_speed")?;
+        Ok(Self::Record { file, frames: 0 })
+    }
+
+    pub fn replay(recording: &str) -> Result<Self, RecordingError> {
+        let mut keyframes = vec![];
+        for (index, line) in recording.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let error = |message: String| RecordingError {
+                line: index + 1,
+                message,
+            };
+            let fields: Vec<&str> = line.split_whitespace().collect();
+            let [cloud_frames, wind_offset, wind_speed] = fields[..] else {
+                return Err(error(format!("expected three values, got `{line}`")));
+            };
+            let parse = |value: &str| {
+                value
+                    .parse::<f64>()
+                    .map_err(|_| error(format!("invalid value `{value}`")))
+            };
+            keyframes.push(CloudKeyframe {
+                cloud_frames: parse(cloud_frames)?,
+                wind_offset: parse(wind_offset)?,
+                wind_speed: parse(wind_speed)? as f32,
+            });
+        }
+        Ok(Self::Replay { keyframes, next: 0 })
+    }
+
+    /// Writes this frame's `keyframe` when recording. When replaying,
+    /// replaces it with the next recorded frame instead, leaving it alone
+    /// once the recording runs out so the clouds carry on live from there.
+    pub fn apply(&mut self, keyframe: &mut CloudKeyframe) -> io::Result<()> {
+        match self {
+            Self::Record { file, frames } => {
+                *frames += 1;
+                writeln!(
+                    file,
+                    "{} {} {}",
+                    keyframe.cloud_frames, keyframe.wind_offset, keyframe.wind_speed
+                )
+            }
+            Self::Replay { keyframes, next } => {
+                if let Some(recorded) = keyframes.get(*next) {
+                    *keyframe = *recorded;
+                    *next += 1;
+                }
+                Ok(())
+            }
+        }
+    }
+
+    /// One-line summary for the overlay.
+    pub fn status(&self) -> String {
+        match self {
+            Self::Record { frames, .. } => format!("recording, {frames} frames"),
+            Self::Replay { keyframes, next } if *next < keyframes.len() => {
+                format!("replaying frame {next} of {}", keyframes.len())
+            }
+            Self::Replay { .. } => "replay finished".to_string(),
+        }
+    }
+}
@@ -0,0 +1,109 @@
+// Computes the raw cloud noise grid on the GPU: the same Billow-over-Perlin
+// fractal and Exponent modifier the CPU path samples through `noise`.
+
+[[block]]
+struct Uniforms {
+    size: u32;
+    octaves: u32;
+    persistence: f32;
+    lacunarity: f32;
+    // Per-octave sample origin, already scaled by the octave's frequency and
+    // wrapped into the 256-cell period of the permutation table, so large
+    // wind offsets don't eat into f32 precision.
+    origins: array<vec4<f32>, 8>;
+    spacing: f32;
+};
+
+[[block]]
+struct Tables {
+    values: array<u32>;
+};
+
+[[block]]
+struct Output {
+    values: array<f32>;
+};
+
+[[group(0), binding(0)]] var<uniform> uniforms: Uniforms;
+[[group(0), binding(1)]] var<storage, read> tables: Tables;
+[[group(0), binding(2)]] var<storage, read_write> output: Output;
+
+fn perm(octave: u32, i: i32) -> i32 {
+    return i32(tables.values[octave * 256u + u32(i & 255)]);
+}
+
+fn hash3(octave: u32, corner: vec3<i32>) -> i32 {
+    let xy = perm(octave, perm(octave, corner.x) ^ (corner.y & 255));
+    return perm(octave, xy ^ (corner.z & 255));
+}
+
+fn gradient_dot(hash: i32, p: vec3<f32>) -> f32 {
+    switch (hash & 15) {
+        case 0, 12: { return p.x + p.y; }
+        case 1, 13: { return -p.x + p.y; }
+        case 2: { return p.x - p.y; }
+        case 3: { return -p.x - p.y; }
+        case 4: { return p.x + p.z; }
+        case 5: { return -p.x + p.z; }
+        case 6: { return p.x - p.z; }
+        case 7: { return -p.x - p.z; }
+        case 8: { return p.y + p.z; }
+        case 9, 14: { return -p.y + p.z; }
+        case 10: { return p.y - p.z; }
+        default: { return -p.y - p.z; }
+    }
+}
+
+fn s_curve5(t: vec3<f32>) -> vec3<f32> {
+    return t * t * t * (t * (t * 6.0 - 15.0) + 10.0);
+}
+
+fn perlin(octave: u32, point: vec3<f32>) -> f32 {
+    let floored = floor(point);
+    let near = vec3<i32>(floored);
+    let far = near + vec3<i32>(1, 1, 1);
+    let d0 = point - floored;
+    let d1 = d0 - vec3<f32>(1.0, 1.0, 1.0);
+
+    let g000 = gradient_dot(hash3(octave, near), d0);
+    let g100 = gradient_dot(hash3(octave, vec3<i32>(far.x, near.y, near.z)), vec3<f32>(d1.x, d0.y, d0.z));
+    let g010 = gradient_dot(hash3(octave, vec3<i32>(near.x, far.y, near.z)), vec3<f32>(d0.x, d1.y, d0.z));
+    let g110 = gradient_dot(hash3(octave, vec3<i32>(far.x, far.y, near.z)), vec3<f32>(d1.x, d1.y, d0.z));
+    let g001 = gradient_dot(hash3(octave, vec3<i32>(near.x, near.y, far.z)), vec3<f32>(d0.x, d0.y, d1.z));
+    let g101 = gradient_dot(hash3(octave, vec3<i32>(far.x, near.y, far.z)), vec3<f32>(d1.x, d0.y, d1.z));
+    let g011 = gradient_dot(hash3(octave, vec3<i32>(near.x, far.y, far.z)), vec3<f32>(d0.x, d1.y, d1.z));
+    let g111 = gradient_dot(hash3(octave, far), d1);
+
+    let s = s_curve5(d0);
+    let k1 = g100 - g000;
+    let k2 = g010 - g000;
+    let k3 = g001 - g000;
+    let k4 = g000 + g110 - g100 - g010;
+    let k5 = g000 + g101 - g100 - g001;
+    let k6 = g000 + g011 - g010 - g001;
+    let k7 = g100 + g010 + g001 + g111 - g000 - g110 - g101 - g011;
+    let value = g000 + k1 * s.x + k2 * s.y + k3 * s.z + k4 * s.x * s.y + k5 * s.x * s.z
+        + k6 * s.y * s.z + k7 * s.x * s.y * s.z;
+    return clamp(value * 1.1547005, -1.0, 1.0);
+}
+
+[[stage(compute), workgroup_size(8, 8)]]
+fn main([[builtin(global_invocation_id)]] id: vec3<u32>) {
+    if (id.x >= uniforms.size || id.y >= uniforms.size) {
+        return;
+    }
+    let local = vec3<f32>(f32(id.x), f32(id.y), 0.0) * uniforms.spacing;
+    var result = 0.0;
+    var frequency = 1.0;
+    var amplitude = 1.0;
+    for (var octave: u32 = 0u; octave < uniforms.octaves; octave = octave + 1u) {
+        let point = uniforms.origins[octave].xyz + local * frequency;
+        result = result + (abs(perlin(octave, point)) * 2.0 - 1.0) * amplitude;
+        frequency = frequency * uniforms.lacunarity;
+        amplitude = amplitude * uniforms.persistence;
+    }
+    let billow = result * 0.5;
+    // Exponent with its default exponent of 1.
+    let exponent = abs((billow + 1.0) / 2.0) * 2.0 - 1.0;
+    output.values[id.x * uniforms.size + id.y] = abs(exponent);
+}
@@ -0,0 +1,765 @@
+use nannou::color::Srgb;
+use std::fmt;
+use std::path::PathBuf;
+
+use crate::easing::Easing;
+use crate::falloff::Falloff;
+use crate::layers::{Layer, DEFAULT_LAYERS};
+use crate::moon_texture::MoonTextureParams;
+use crate::post::{ChannelCurve, ColorCurves, ToneCurve};
+use crate::{CLOUD_DAY_COLOR, CLOUD_NIGHT_COLOR, NOISE_SCALE, NUM_POINTS, SCREEN_SIZE_F};
+
+pub const DEFAULT_SCATTERING: f32 = 0.15;
+pub const DEFAULT_BLOOM_STRENGTH: f32 = 0.8;
+pub const DEFAULT_BLOOM_THRESHOLD: f32 = 0.75;
+/// Highest `--bloom-strength`, which adds the blurred highlights at twice
+/// their own brightness.
+pub const MAX_BLOOM_STRENGTH: f32 = 2.;
+/// Longest `--instant-replay`, about 290 MiB of kept frames.
+pub const MAX_INSTANT_REPLAY_SECONDS: f32 = 60.;
+pub const DEFAULT_FRAME_BUDGET_MS: f32 = 16.;
+pub const DEFAULT_CLOUD_SHADING: f32 = 0.25;
+/// Leaves the night sky at the palette's night color.
+pub const DEFAULT_NIGHT_DARKNESS: f32 = 0.5;
+/// Burn-off runs to about mid-afternoon, a sixth of the day after sunrise.
+pub const DEFAULT_BURN_OFF_DURATION: f32 = 0.16;
+/// Exposure at night, with the sun on the horizon, and at noon. All 1
+/// leaves the scene as bright as the sky colors alone make it.
+pub const NEUTRAL_SUN_EXPOSURE: [f32; 3] = [1., 1., 1.];
+/// Sun path heights for `--season`, relative to the default circle.
+pub const SUMMER_SUN_ARC: f32 = 1.15;
+pub const WINTER_SUN_ARC: f32 = 0.6;
+/// The highest arc that keeps the whole sun disc inside the window at noon.
+/// The sun's center is then `SUN_START_Y` times the arc up, 414 of 450 at
+/// 1.15, leaving room for its 30 pixel radius but not all of its aura.
+pub const MAX_SUN_ARC: f32 = 1.15;
+/// A full moon, rising as the sun sets.
+pub const DEFAULT_MOON_OFFSET: f32 = 180.;
+
+/// Settings parsed from the command line at startup.
+pub struct Config {
+    pub loop_window: Option<LoopWindow>,
+    /// Strength of the blue atmospheric-perspective wash near the horizon.
+    pub scattering: f32,
+    /// How much stars near the horizon redden and dim from `--star-extinction`,
+    /// 0 to 1. Stars overhead are unaffected; 0 leaves every star white.
+    pub star_extinction: f32,
+    /// Base cloud colors. Weather effects such as storminess darken these
+    /// rather than replacing them.
+    pub cloud_day_color: Srgb<u8>,
+    pub cloud_night_color: Srgb<u8>,
+    /// How much dense cloud darkens itself, 0 for flat color and 1 for
+    /// black cores.
+    pub cloud_shading: f32,
+    /// Domain warp strength from `--warp`, in noise units, where a window
+    /// is about 0.27 wide; 0.02 to 0.05 gives visible curls. 0 leaves the
+    /// cloud shapes unwarped.
+    pub warp_strength: f64,
+    /// How much of the cloud cover burns off after sunrise, 0 to 1, and over
+    /// what fraction of the day it burns off and recovers, see
+    /// `Model::burn_off`. 0 leaves the clouds alone.
+    pub burn_off: f32,
+    pub burn_off_duration: f32,
+    /// Vary cloud density and storminess across the width of the window
+    /// instead of using one global state, see `WeatherZones`.
+    pub weather_zones: bool,
+    /// Where the wind blows the clouds, in degrees anticlockwise from
+    /// rightwards. Any upward or downward part rules out `--wrap-clouds`
+    /// and scrolling, which only move the field sideways.
+    pub wind_direction: f32,
+    /// Let the wind speed rise and fall around the set value instead of
+    /// holding steady.
+    pub gusts: bool,
+    /// Draw the Milky Way at night from the start; `M` still toggles it.
+    pub milky_way: bool,
+    pub background: Background,
+    /// Script of timed actions to play back, see `Timeline`.
+    pub timeline: Option<PathBuf>,
+    /// File to record the cloud clock and wind to, or replay them from.
+    pub cloud_recording: Option<CloudRecordingMode>,
+    /// Seconds of sky to keep for `K` to save, from `--instant-replay`.
+    /// Each second kept costs about 4.8 MiB, see `InstantReplay`, up to
+    /// `MAX_INSTANT_REPLAY_SECONDS`.
+    pub instant_replay: Option<f32>,
+    /// Bloom settings used while bloom is toggled on with `B`.
+    pub bloom_strength: f32,
+    pub bloom_threshold: f32,
+    /// Tone mapping used while toggled on with `X`.
+    pub tone_curve: ToneCurve,
+    pub tone_map_strength: f32,
+    /// Scene exposure at night, sunrise and sunset, and noon, from
+    /// `--sun-exposure`. Something like `0.7,0.85,1.1` dims dusk and washes
+    /// out midday; see `Sun::exposure`.
+    pub sun_exposure: [f32; 3],
+    /// Per-channel grading from `--color-curves`, applied to the final frame
+    /// after tone mapping. The curves see the compressed image, so with
+    /// tone mapping on a gain below 1 dims the highlights further rather
+    /// than restoring their range.
+    pub color_curves: ColorCurves,
+    /// Moon illumination, 0 at new moon and 1 at full. Scales how brightly
+    /// the moon lights nearby clouds.
+    pub moon_phase: f32,
+    /// Where the cloud noise is sampled; `G` switches at runtime.
+    pub cloud_backend: CloudBackend,
+    pub projection: Projection,
+    /// Scroll the cloud grid with the wind between full resamples instead
+    /// of resampling every frame, see `Model::update_clouds`.
+    pub scroll_clouds: bool,
+    /// Accessibility mode from `--reduce-motion`. Cloud drift is held to
+    /// half the default wind without `--gusts`, which also keeps the clouds
+    /// from stretching; the sun and cloud clocks can't be sped past normal
+    /// and `Right` no longer fast-forwards; stars stop twinkling; and the
+    /// green flash and `--breathe` zoom are turned off. The day cycle and
+    /// its sky colors carry on as usual.
+    pub reduce_motion: bool,
+    /// How dark the sky gets at full night, from a lifted navy at 0 to
+    /// pitch black at 1.
+    pub night_darkness: f32,
+    /// Curve the sky color and star fade follow through sunrise and sunset.
+    pub sky_easing: Easing,
+    /// How the sun, moon and star auras fade outwards.
+    pub aura_falloff: Falloff,
+    /// CSV of real star positions to show instead of a random sky, see
+    /// `CatalogStar`.
+    pub stars_file: Option<PathBuf>,
+    /// Frame budget in milliseconds when profiling with `--profile`, which
+    /// can be followed by a budget to override the default.
+    pub frame_budget: Option<f32>,
+    /// Period, in noise units of wind drift, after which the cloud field
+    /// repeats. Set from `--wrap-clouds` in window widths. Shapes still
+    /// evolve over time, so exact repeats also need time to loop, as with
+    /// `--loop`. Sampled on the CPU even when the GPU backend is selected.
+    pub cloud_wrap: Option<f64>,
+    /// Height of the sun's path relative to the default circle, from
+    /// `--sun-arc` or `--season`: lower and flatter in winter, higher in
+    /// summer. Sunrise and sunset stay where they are.
+    pub sun_arc: f32,
+    /// When set the moon follows the sun's path, trailing it by this many
+    /// degrees, so 90 rises around noon and 180 rises at sunset. Otherwise
+    /// it stays put.
+    pub moon_orbit: Option<f32>,
+    /// Take the sky through civil, nautical and astronomical twilight after
+    /// sunset, with stars appearing in order of brightness, instead of going
+    /// straight to night.
+    pub twilight: bool,
+    /// Layers to draw, back to front. Layers left out of `--layers` are
+    /// never drawn.
+    pub layers: Vec<Layer>,
+    /// Slowly zoom the scene in and out, for ambient displays.
+    pub breathe: bool,
+    /// Sky gradient from midday to night, spaced evenly, in place of the
+    /// palette's day, sunset and night colors. Presets then no longer change
+    /// the sky.
+    pub sky_stops: Option<Vec<Srgb<u8>>>,
+    /// Look of the moon's surface; `R` swaps in random variations.
+    pub moon_texture: MoonTextureParams,
+    /// Start with smooth, interpolated clouds rather than splats.
+    pub smooth_clouds: bool,
+    /// Width in pixels of the band along the window edges over which
+    /// clouds fade out. 0 lets them run to the edges.
+    pub edge_feather: f32,
+}
+
+/// What the frame is cleared to behind the sun, moon, stars and clouds.
+///
+/// With `Transparent` the window shows the cleared areas as black, but PNG
+/// captures (`C`) keep the alpha channel, so the sky elements can be laid
+/// over other footage directly. Soft edges such as auras and thin clouds are
+/// stored as partial alpha. `Chroma` fills the backdrop with a flat key
+/// color instead, for tools that don't read alpha.
+#[derive(Clone, Copy, PartialEq)]
+pub enum Background {
+    Sky,
+    Transparent,
+    Chroma(Srgb<u8>),
+}
+
+/// How the sky is laid out on screen.
+#[derive(Clone, Copy, PartialEq)]
+pub enum Projection {
+    /// The sky as a flat window, horizon along the bottom edge.
+    Flat,
+    /// A fisheye view looking straight up, with the horizon as a circle
+    /// around the edge of the window, for planetarium-style displays.
+    Dome,
+}
+
+/// Whether `--record-clouds` or `--replay-clouds` was given, see
+/// `CloudRecording`.
+pub enum CloudRecordingMode {
+    Record(PathBuf),
+    Replay(PathBuf),
+}
+
+/// Which processor samples the cloud noise grid.
+#[derive(Clone, Copy, PartialEq)]
+pub enum CloudBackend {
+    /// Rayon-parallel sampling through `noise`.
+    Cpu,
+    /// A compute shader, see `GpuClouds`.
+    Gpu,
+}
+
+impl CloudBackend {
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Cpu => "cpu",
+            Self::Gpu => "gpu",
+        }
+    }
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            loop_window: None,
+            scattering: DEFAULT_SCATTERING,
+            star_extinction: 0.,
+            cloud_day_color: CLOUD_DAY_COLOR,
+            cloud_night_color: CLOUD_NIGHT_COLOR,
+            cloud_shading: DEFAULT_CLOUD_SHADING,
+            warp_strength: 0.,
+            weather_zones: false,
+            wind_direction: 0.,
+            gusts: false,
+            milky_way: false,
+            burn_off: 0.,
+            burn_off_duration: DEFAULT_BURN_OFF_DURATION,
+            background: Background::Sky,
+            timeline: None,
+            cloud_recording: None,
+            instant_replay: None,
+            bloom_strength: DEFAULT_BLOOM_STRENGTH,
+            bloom_threshold: DEFAULT_BLOOM_THRESHOLD,
+            tone_curve: ToneCurve::Aces,
+            tone_map_strength: 1.,
+            sun_exposure: NEUTRAL_SUN_EXPOSURE,
+            color_curves: ColorCurves::IDENTITY,
+            moon_phase: 1.,
+            cloud_backend: CloudBackend::Cpu,
+            projection: Projection::Flat,
+            scroll_clouds: false,
+            reduce_motion: false,
+            night_darkness: DEFAULT_NIGHT_DARKNESS,
+            sky_easing: Easing::default(),
+            aura_falloff: Falloff::default(),
+            stars_file: None,
+            frame_budget: None,
+            cloud_wrap: None,
+            sun_arc: 1.,
+            moon_orbit: None,
+            twilight: false,
+            layers: DEFAULT_LAYERS.to_vec(),
+            breathe: false,
+            sky_stops: None,
+            moon_texture: MoonTextureParams::default(),
+            smooth_clouds: false,
+            edge_feather: 0.,
+        }
+    }
+}
+
+/// A slice of the day cycle, in normalized time (0 is the start of the cycle,
+/// 1 is a full revolution of the sun), that the simulation repeats forever.
+#[derive(Clone, Copy)]
+pub struct LoopWindow {
+    pub start: f64,
+    pub end: f64,
+}
+
+impl LoopWindow {
+    pub fn len(&self) -> f64 {
+        self.end - self.start
+    }
+}
+
+#[derive(Debug)]
+pub struct ConfigError(String);
+
+impl fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl Config {
+    pub fn from_args() -> Result<Self, ConfigError> {
+        Self::parse(std::env::args().skip(1))
+    }
+
+    pub fn parse(args: impl IntoIterator<Item = String>) -> Result<Self, ConfigError> {
+        let mut config = Config::default();
+        let mut args = args.into_iter().peekable();
+        while let Some(arg) = args.next() {
+            match arg.as_str() {
+                "--loop" => {
+                    let start = parse_value(&arg, args.next())?;
+                    let end = parse_value(&arg, args.next())?;
+                    if !(0. ..=1.).contains(&start) || !(0. ..=1.).contains(&end) || start >= end {
+                        return Err(ConfigError(format!(
+                            "--loop expects 0 <= start < end <= 1, got {start} {end}"
+                        )));
+                    }
+                    config.loop_window = Some(LoopWindow { start, end });
+                }
+                "--scattering" => {
+                    config.scattering = parse_value(&arg, args.next())?;
+                    if !(0. ..=1.).contains(&config.scattering) {
+                        return Err(ConfigError(format!(
+                            "--scattering expects a value between 0 and 1, got {}",
+                            config.scattering
+                        )));
+                    }
+                }
+                "--star-extinction" => {
+                    config.star_extinction = parse_value(&arg, args.next())?;
+                    if !(0. ..=1.).contains(&config.star_extinction) {
+                        return Err(ConfigError(format!(
+                            "--star-extinction expects a value between 0 and 1, got {}",
+                            config.star_extinction
+                        )));
+                    }
+                }
+                "--cloud-day-color" => {
+                    config.cloud_day_color = parse_color(&arg, args.next())?;
+                }
+                "--cloud-night-color" => {
+                    config.cloud_night_color = parse_color(&arg, args.next())?;
+                }
+                "--cloud-shading" => {
+                    config.cloud_shading = parse_value(&arg, args.next())?;
+                    if !(0. ..=1.).contains(&config.cloud_shading) {
+                        return Err(ConfigError(format!(
+                            "--cloud-shading expects a value between 0 and 1, got {}",
+                            config.cloud_shading
+                        )));
+                    }
+                }
+                "--warp" => {
+                    config.warp_strength = parse_value(&arg, args.next())?;
+                    if config.warp_strength < 0. {
+                        return Err(ConfigError(format!(
+                            "--warp expects a non-negative strength, got {}",
+                            config.warp_strength
+                        )));
+                    }
+                }
+                "--profile" => {
+                    let budget: f32 =
+                        parse_optional_value(&mut args).unwrap_or(DEFAULT_FRAME_BUDGET_MS);
+                    if !budget.is_finite() || budget <= 0. {
+                        return Err(ConfigError(format!(
+                            "--profile expects a frame budget above 0 milliseconds, got {budget}"
+                        )));
+                    }
+                    config.frame_budget = Some(budget);
+                }
+                "--sun-arc" => {
+                    config.sun_arc = parse_value(&arg, args.next())?;
+                    if !(0.1..=MAX_SUN_ARC).contains(&config.sun_arc) {
+                        return Err(ConfigError(format!(
+                            "--sun-arc expects a height between 0.1 and {MAX_SUN_ARC}, got {}",
+                            config.sun_arc
+                        )));
+                    }
+                }
+                "--season" => {
+                    let value: String = parse_value(&arg, args.next())?;
+                    config.sun_arc = match value.as_str() {
+                        "summer" => SUMMER_SUN_ARC,
+                        "winter" => WINTER_SUN_ARC,
+                        "spring" | "autumn" => 1.,
+                        _ => {
+                            return Err(ConfigError(format!(
+                                "--season expects spring, summer, autumn or winter, got `{value}`"
+                            )))
+                        }
+                    };
+                }
+                "--moon-orbit" => {
+                    let offset: f32 =
+                        parse_optional_value(&mut args).unwrap_or(DEFAULT_MOON_OFFSET);
+                    if !offset.is_finite() {
+                        return Err(ConfigError(format!(
+                            "--moon-orbit expects an offset in degrees, got {offset}"
+                        )));
+                    }
+                    config.moon_orbit = Some(offset.rem_euclid(360.));
+                }
+                "--wind-direction" => {
+                    let degrees: f32 = parse_value(&arg, args.next())?;
+                    if !degrees.is_finite() {
+                        return Err(ConfigError(format!(
+                            "--wind-direction expects an angle in degrees, got {degrees}"
+                        )));
+                    }
+                    config.wind_direction = degrees.rem_euclid(360.);
+                }
+                "--wrap-clouds" => {
+                    let widths: f64 = parse_value(&arg, args.next())?;
+                    if !widths.is_finite() || widths <= 0. {
+                        return Err(ConfigError(format!(
+                            "--wrap-clouds expects a positive number of window widths, got {widths}"
+                        )));
+                    }
+                    config.cloud_wrap = Some(widths * NUM_POINTS as f64 / NOISE_SCALE);
+                }
+                "--layers" => {
+                    let value: String = parse_value(&arg, args.next())?;
+                    config.layers = vec![];
+                    for name in value.split(',') {
+                        let layer = Layer::from_name(name).ok_or_else(|| {
+                            let names: Vec<_> = DEFAULT_LAYERS.iter().map(|l| l.name()).collect();
+                            ConfigError(format!(
+                                "--layers: unknown layer `{name}`, expected a comma-separated list of {}",
+                                names.join(", ")
+                            ))
+                        })?;
+                        if config.layers.contains(&layer) {
+                            return Err(ConfigError(format!("--layers: `{name}` listed twice")));
+                        }
+                        config.layers.push(layer);
+                    }
+                }
+                "--sky-stops" => {
+                    let value: String = parse_value(&arg, args.next())?;
+                    let stops = value
+                        .split(',')
+                        .map(|stop| parse_color(&arg, Some(stop.to_string())))
+                        .collect::<Result<Vec<_>, _>>()?;
+                    if stops.len() < 2 {
+                        return Err(ConfigError(format!(
+                            "--sky-stops expects at least two comma-separated colors, got `{value}`"
+                        )));
+                    }
+                    config.sky_stops = Some(stops);
+                }
+                "--feather-edges" => {
+                    config.edge_feather = parse_value(&arg, args.next())?;
+                    if !(0. ..=SCREEN_SIZE_F / 2.).contains(&config.edge_feather) {
+                        return Err(ConfigError(format!(
+                            "--feather-edges expects a margin between 0 and {} pixels, got {}",
+                            SCREEN_SIZE_F / 2.,
+                            config.edge_feather
+                        )));
+                    }
+                }
+                "--stars-file" => config.stars_file = Some(parse_value(&arg, args.next())?),
+                "--timeline" => config.timeline = Some(parse_value(&arg, args.next())?),
+                "--record-clouds" => {
+                    config.cloud_recording =
+                        Some(CloudRecordingMode::Record(parse_value(&arg, args.next())?));
+                }
+                "--instant-replay" => {
+                    let seconds: f32 = parse_value(&arg, args.next())?;
+                    if !(seconds > 0. && seconds <= MAX_INSTANT_REPLAY_SECONDS) {
+                        return Err(ConfigError(format!(
+                            "--instant-replay expects a number of seconds above 0 and up to \
+                             {MAX_INSTANT_REPLAY_SECONDS}, got {seconds}"
+                        )));
+                    }
+                    config.instant_replay = Some(seconds);
+                }
+                "--replay-clouds" => {
+                    config.cloud_recording =
+                        Some(CloudRecordingMode::Replay(parse_value(&arg, args.next())?));
+                }
+                "--bloom-strength" => {
+                    config.bloom_strength = parse_value(&arg, args.next())?;
+                    if !(0. ..=MAX_BLOOM_STRENGTH).contains(&config.bloom_strength) {
+                        return Err(ConfigError(format!(
+                            "--bloom-strength expects a value between 0 and {MAX_BLOOM_STRENGTH}, got {}",
+                            config.bloom_strength
+                        )));
+                    }
+                }
+                "--bloom-threshold" => {
+                    config.bloom_threshold = parse_value(&arg, args.next())?;
+                    if !(0. ..=1.).contains(&config.bloom_threshold) {
+                        return Err(ConfigError(format!(
+                            "--bloom-threshold expects a luminance between 0 and 1, got {}",
+                            config.bloom_threshold
+                        )));
+                    }
+                }
+                "--tone-map" => {
+                    let value: String = parse_value(&arg, args.next())?;
+                    config.tone_curve = ToneCurve::from_name(&value).ok_or_else(|| {
+                        ConfigError(format!(
+                            "--tone-map expects reinhard or aces, got `{value}`"
+                        ))
+                    })?;
+                }
+                "--tone-map-strength" => {
+                    config.tone_map_strength = parse_value(&arg, args.next())?;
+                    if !(0. ..=1.).contains(&config.tone_map_strength) {
+                        return Err(ConfigError(format!(
+                            "--tone-map-strength expects a value between 0 and 1, got {}",
+                            config.tone_map_strength
+                        )));
+                    }
+                }
+                "--sun-exposure" => {
+                    let value: String = parse_value(&arg, args.next())?;
+                    let stops = value
+                        .split(',')
+                        .map(|stop| stop.parse::<f32>().ok().filter(|&v| v >= 0.))
+                        .collect::<Option<Vec<_>>>();
+                    config.sun_exposure = match stops.as_deref() {
+                        Some(&[night, horizon, noon]) => [night, horizon, noon],
+                        _ => {
+                            return Err(ConfigError(format!(
+                                "--sun-exposure expects night, horizon and noon multipliers \
+                                 like `0.7,0.85,1.1`, got `{value}`"
+                            )))
+                        }
+                    };
+                }
+                "--color-curves" => {
+                    let value: String = parse_value(&arg, args.next())?;
+                    config.color_curves = match ColorCurves::from_name(&value) {
+                        Some(curves) => curves,
+                        None => parse_color_curves(&value)?,
+                    };
+                }
+                "--moon-phase" => {
+                    config.moon_phase = parse_value(&arg, args.next())?;
+                    if !(0. ..=1.).contains(&config.moon_phase) {
+                        return Err(ConfigError(format!(
+                            "--moon-phase expects a value between 0 and 1, got {}",
+                            config.moon_phase
+                        )));
+                    }
+                }
+                "--clouds" => {
+                    let value: String = parse_value(&arg, args.next())?;
+                    config.cloud_backend = match value.as_str() {
+                        "cpu" => CloudBackend::Cpu,
+                        "gpu" => CloudBackend::Gpu,
+                        _ => {
+                            return Err(ConfigError(format!(
+                                "--clouds expects cpu or gpu, got `{value}`"
+                            )))
+                        }
+                    };
+                }
+                "--moon-texture" => {
+                    let value: String = parse_value(&arg, args.next())?;
+                    config.moon_texture = MoonTextureParams::from_name(&value).ok_or_else(|| {
+                        ConfigError(format!(
+                            "--moon-texture expects default, smooth, cratered or mottled, got `{value}`"
+                        ))
+                    })?;
+                }
+                "--easing" => {
+                    let value: String = parse_value(&arg, args.next())?;
+                    config.sky_easing = Easing::from_name(&value).ok_or_else(|| {
+                        ConfigError(format!(
+                            "--easing expects linear, ease-in, ease-out, ease-in-out, \
+                             exponential or log, got `{value}`"
+                        ))
+                    })?;
+                }
+                "--burn-off" => {
+                    config.burn_off = parse_value(&arg, args.next())?;
+                    if !(0. ..=1.).contains(&config.burn_off) {
+                        return Err(ConfigError(format!(
+                            "--burn-off expects a value between 0 and 1, got {}",
+                            config.burn_off
+                        )));
+                    }
+                }
+                "--burn-off-duration" => {
+                    config.burn_off_duration = parse_value(&arg, args.next())?;
+                    if !(config.burn_off_duration > 0. && config.burn_off_duration <= 1.) {
+                        return Err(ConfigError(format!(
+                            "--burn-off-duration expects a fraction of the day above 0 and \
+                             up to 1, got {}",
+                            config.burn_off_duration
+                        )));
+                    }
+                }
+                "--night-darkness" => {
+                    config.night_darkness = parse_value(&arg, args.next())?;
+                    if !(0. ..=1.).contains(&config.night_darkness) {
+                        return Err(ConfigError(format!(
+                            "--night-darkness expects a value between 0 and 1, got {}",
+                            config.night_darkness
+                        )));
+                    }
+                }
+                "--aura-falloff" => {
+                    let value: String = parse_value(&arg, args.next())?;
+                    config.aura_falloff = Falloff::from_name(&value).ok_or_else(|| {
+                        ConfigError(format!(
+                            "--aura-falloff expects linear, exponential, inverse-square \
+                             or log, got `{value}`"
+                        ))
+                    })?;
+                }
+                "--scroll-clouds" => config.scroll_clouds = true,
+                "--twilight" => config.twilight = true,
+                "--breathe" => config.breathe = true,
+                "--smooth-clouds" => config.smooth_clouds = true,
+                "--weather-zones" => config.weather_zones = true,
+                "--gusts" => config.gusts = true,
+                "--milky-way" => config.milky_way = true,
+                "--reduce-motion" => config.reduce_motion = true,
+                "--dome" => config.projection = Projection::Dome,
+                "--transparent" => config.background = Background::Transparent,
+                "--chroma" => {
+                    config.background = Background::Chroma(parse_color(&arg, args.next())?);
+                }
+                _ => return Err(ConfigError(format!("unknown argument `{arg}`"))),
+            }
+        }
+        if config.cloud_wrap.is_some() && config.wind_direction % 180. != 0. {
+            return Err(ConfigError(
+                "--wrap-clouds needs the wind blowing straight left or right".to_string(),
+            ));
+        }
+        Ok(config)
+    }
+}
+
+fn parse_value<T: std::str::FromStr>(flag: &str, value: Option<String>) -> Result<T, ConfigError> {
+    let value = value.ok_or_else(|| ConfigError(format!("{flag} is missing a value")))?;
+    value
+        .parse()
+        .map_err(|_| ConfigError(format!("{flag}: invalid value `{value}`")))
+}
+
+/// Takes the next argument if it parses as a value, leaving flags alone.
+fn parse_optional_value<T: std::str::FromStr>(
+    args: &mut std::iter::Peekable<impl Iterator<Item = String>>,
+) -> Option<T> {
+    let value = args.peek()?.parse().ok()?;
+    args.next();
+    Some(value)
+}
+
+/// Parses custom curves like `r=0.05:1:1,b=0:1.2:0.85`, giving lift, gamma
+/// and gain for any of the `r`, `g` and `b` channels. Channels left out keep
+/// the identity curve.
+fn parse_color_curves(value: &str) -> Result<ColorCurves, ConfigError> {
+    let error = || {
+        ConfigError(format!(
+            "--color-curves expects identity, vintage, eerie, cool or curves like \
+             `r=0.05:1:1,b=0:1.2:0.85`, got `{value}`"
+        ))
+    };
+    let mut curves = ColorCurves::IDENTITY;
+    for part in value.split(',') {
+        let (channel, params) = part.split_once('=').ok_or_else(error)?;
+        let params = params
+            .split(':')
+            .map(|p| p.parse::<f32>().map_err(|_| error()))
+            .collect::<Result<Vec<_>, _>>()?;
+        let curve = match params[..] {
+            [lift, gamma, gain] if gamma > 0. => ChannelCurve { lift, gamma, gain },
+            _ => return Err(error()),
+        };
+        match channel {
+            "r" => curves.red = curve,
+            "g" => curves.green = curve,
+            "b" => curves.blue = curve,
+            _ => return Err(error()),
+        }
+    }
+    Ok(curves)
+}
+
+/// Parses a `rrggbb` hex color, with or without a leading `#`.
+fn parse_color(flag: &str, value: Option<String>) -> Result<Srgb<u8>, ConfigError> {
+    let value: String = parse_value(flag, value)?;
+    let hex = value.strip_prefix('#').unwrap_or(&value);
+    let channel = |i: usize| {
+        hex.get(i..i + 2)
+            .and_then(|c| u8::from_str_radix(c, 16).ok())
+    };
+    match (hex.len(), channel(0), channel(2), channel(4)) {
+        (6, Some(red), Some(green), Some(blue)) => Ok(Srgb::new(red, green, blue)),
+        _ => Err(ConfigError(format!(
+            "{flag}: expected a hex color like `#ffc0cb`, got `{value}`"
+        ))),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(args: &str) -> Result<Config, ConfigError> {
+        Config::parse(args.split_whitespace().map(String::from))
+    }
+
+    #[test]
+    fn profile_rejects_bad_budgets() {
+        for args in [
+            "--profile 0",
+            "--profile -5",
+            "--profile NaN",
+            "--profile inf",
+        ] {
+            assert!(parse(args).is_err(), "accepted `{args}`");
+        }
+    }
+
+    #[test]
+    fn bloom_settings_are_range_checked() {
+        for args in [
+            "--bloom-strength -1",
+            "--bloom-strength NaN",
+            "--bloom-strength inf",
+            "--bloom-threshold -0.1",
+            "--bloom-threshold NaN",
+            "--bloom-threshold 1.5",
+        ] {
+            assert!(parse(args).is_err(), "accepted `{args}`");
+        }
+        assert!(parse("--bloom-strength 2 --bloom-threshold 0").is_ok());
+    }
+
+    #[test]
+    fn wrap_clouds_rejects_non_finite_widths() {
+        for args in ["--wrap-clouds 0", "--wrap-clouds NaN", "--wrap-clouds inf"] {
+            assert!(parse(args).is_err(), "accepted `{args}`");
+        }
+        assert!(parse("--wrap-clouds 2").is_ok());
+    }
+
+    #[test]
+    fn instant_replay_is_capped() {
+        for args in [
+            "--instant-replay 0",
+            "--instant-replay NaN",
+            "--instant-replay inf",
+            "--instant-replay 61",
+        ] {
+            assert!(parse(args).is_err(), "accepted `{args}`");
+        }
+        assert!(parse("--instant-replay 60").is_ok());
+    }
+
+    #[test]
+    fn angles_must_be_finite() {
+        for args in [
+            "--moon-orbit NaN",
+            "--moon-orbit inf",
+            "--wind-direction NaN",
+            "--wind-direction -inf",
+        ] {
+            assert!(parse(args).is_err(), "accepted `{args}`");
+        }
+        assert_eq!(parse("--moon-orbit -90").unwrap().moon_orbit, Some(270.));
+    }
+
+    #[test]
+    fn profile_budget_is_optional() {
+        assert_eq!(
+            parse("--profile").unwrap().frame_budget,
+            Some(DEFAULT_FRAME_BUDGET_MS)
+        );
+        assert_eq!(parse("--profile 8").unwrap().frame_budget, Some(8.));
+    }
+}
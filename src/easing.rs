@@ -0,0 +1,46 @@
+/// Shapes a normalized transition value, mapping 0..=1 onto 0..=1.
+#[derive(Clone, Copy, PartialEq, Debug, Default)]
+pub enum Easing {
+    Linear,
+    EaseIn,
+    EaseOut,
+    EaseInOut,
+    Exponential,
+    /// `1 + log10(t)`: holds at 0 for the first tenth, then rises steeply
+    /// and flattens out. The sky has always used this curve.
+    #[default]
+    Logarithmic,
+}
+
+impl Easing {
+    pub fn apply(self, t: f32) -> f32 {
+        let t = t.clamp(0., 1.);
+        let eased = match self {
+            Self::Linear => t,
+            Self::EaseIn => t * t,
+            Self::EaseOut => 1. - (1. - t) * (1. - t),
+            Self::EaseInOut => t * t * (3. - 2. * t),
+            Self::Exponential => {
+                if t <= 0. {
+                    0.
+                } else {
+                    2f32.powf(10. * (t - 1.))
+                }
+            }
+            Self::Logarithmic => 1. - t.log10().abs(),
+        };
+        eased.clamp(0., 1.)
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        Some(match name {
+            "linear" => Self::Linear,
+            "ease-in" => Self::EaseIn,
+            "ease-out" => Self::EaseOut,
+            "ease-in-out" => Self::EaseInOut,
+            "exponential" => Self::Exponential,
+            "log" => Self::Logarithmic,
+            _ => return None,
+        })
+    }
+}
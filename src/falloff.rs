@@ -0,0 +1,48 @@
+/// How a glow's rings fade from the body's edge to the edge of its aura.
+#[derive(Clone, Copy, PartialEq, Debug, Default)]
+pub enum Falloff {
+    /// Fades steadily at the same rate all the way out.
+    Linear,
+    /// Drops quickly near the body, for tight bright glows.
+    Exponential,
+    /// A bright core with a long, faint tail, for broad soft halos.
+    InverseSquare,
+    /// `-log10` of a value rising towards 1: nearly linear for faint auras,
+    /// curving more the brighter the inner ring. The auras have always
+    /// used this curve.
+    #[default]
+    Logarithmic,
+}
+
+/// Sharpness of the exponential and inverse-square curves.
+const EXPONENTIAL_RATE: f64 = 5.;
+const INVERSE_SQUARE_SCALE: f64 = 4.;
+
+impl Falloff {
+    /// Alpha of the ring `t` of the way out through the aura, starting from
+    /// `peak` at the body's edge and reaching 0 at the aura's outer edge.
+    pub fn alpha(self, t: f64, peak: f64) -> f64 {
+        let t = t.clamp(0., 1.);
+        // rescales a decaying curve to run from exactly 1 down to exactly 0
+        let normalize = |f: &dyn Fn(f64) -> f64| (f(t) - f(1.)) / (f(0.) - f(1.));
+        match self {
+            Self::Linear => peak * (1. - t),
+            Self::Exponential => peak * normalize(&|t| (-EXPONENTIAL_RATE * t).exp()),
+            Self::InverseSquare => peak * normalize(&|t| (1. + INVERSE_SQUARE_SCALE * t).powi(-2)),
+            Self::Logarithmic => {
+                let start = 10f64.powf(-peak);
+                -(start + (1. - start) * t).log10()
+            }
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        Some(match name {
+            "linear" => Self::Linear,
+            "exponential" => Self::Exponential,
+            "inverse-square" => Self::InverseSquare,
+            "log" => Self::Logarithmic,
+            _ => return None,
+        })
+    }
+}
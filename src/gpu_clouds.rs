@@ -0,0 +1,183 @@
+use nannou::noise::{Billow, Seedable};
+use nannou::prelude::*;
+use nannou::wgpu::util::DeviceExt;
+use rand::seq::SliceRandom;
+use rand::SeedableRng;
+use rand_xorshift::XorShiftRng;
+
+use crate::{Points, BILLOW_OCTAVES, NOISE_SCALE, NUM_POINTS, Y_OFFSET};
+
+const WORKGROUP_SIZE: u32 = 8;
+const MAX_OCTAVES: usize = 8;
+/// Perlin noise repeats every 256 lattice cells.
+const NOISE_PERIOD: f64 = 256.;
+const UNIFORMS_SIZE: u64 = 160;
+
+const _: () = assert!(BILLOW_OCTAVES <= MAX_OCTAVES);
+
+/// Samples the raw cloud noise with a compute shader (`clouds.wgsl`) and
+/// reads the grid back for the CPU-side thresholding.
+///
+/// The shader carries its own copy of Perlin noise fed with the same
+/// permutation tables `noise` builds for each octave, so the result matches
+/// the CPU path to within f32 precision. Reading back stalls until the GPU
+/// has finished, which costs more than the compute itself at the default
+/// grid size; it pays off on large grids.
+pub struct GpuClouds {
+    uniforms: wgpu::Buffer,
+    output: wgpu::Buffer,
+    readback: wgpu::Buffer,
+    output_size: u64,
+    bind_group: wgpu::BindGroup,
+    pipeline: wgpu::ComputePipeline,
+}
+
+impl GpuClouds {
+    pub fn new(window: &Window, billow: &Billow) -> Self {
+        let device = window.device();
+        let tables: Vec<u8> = (0..billow.octaves as u32)
+            .flat_map(|octave| permutation_table(billow.seed() + octave))
+            .flat_map(|value| (value as u32).to_ne_bytes())
+            .collect();
+        let tables = device.create_buffer_init(&wgpu::BufferInitDescriptor {
+            label: Some("cloud permutation tables"),
+            contents: &tables,
+            usage: wgpu::BufferUsages::STORAGE,
+        });
+        let uniforms = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("cloud uniforms"),
+            size: UNIFORMS_SIZE,
+            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+        let output_size = (NUM_POINTS * NUM_POINTS) as u64 * 4;
+        let output = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("cloud output"),
+            size: output_size,
+            usage: wgpu::BufferUsages::STORAGE | wgpu::BufferUsages::COPY_SRC,
+            mapped_at_creation: false,
+        });
+        let readback = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("cloud readback"),
+            size: output_size,
+            usage: wgpu::BufferUsages::MAP_READ | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+
+        let layout = wgpu::BindGroupLayoutBuilder::new()
+            .uniform_buffer(wgpu::ShaderStages::COMPUTE, false)
+            .storage_buffer(wgpu::ShaderStages::COMPUTE, false, true)
+            .storage_buffer(wgpu::ShaderStages::COMPUTE, false, false)
+            .build(device);
+        let bind_group = wgpu::BindGroupBuilder::new()
+            .buffer_bytes(&uniforms, 0, None)
+            .buffer_bytes(&tables, 0, None)
+            .buffer_bytes(&output, 0, None)
+            .build(device, &layout);
+        let pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("clouds"),
+            bind_group_layouts: &[&layout],
+            push_constant_ranges: &[],
+        });
+        let shader = device.create_shader_module(&wgpu::include_wgsl!("clouds.wgsl"));
+        let pipeline = device.create_compute_pipeline(&wgpu::ComputePipelineDescriptor {
+            label: Some("clouds"),
+            layout: Some(&pipeline_layout),
+            module: &shader,
+            entry_point: "main",
+        });
+
+        Self {
+            uniforms,
+            output,
+            readback,
+            output_size,
+            bind_group,
+            pipeline,
+        }
+    }
+
+    /// The raw noise grid, equivalent to the `raw` half of `generate_clouds`.
+    pub fn generate(&self, window: &Window, billow: &Billow, delta: f64, drift: DVec2) -> Points {
+        let device = window.device();
+        let queue = window.queue();
+        queue.write_buffer(
+            &self.uniforms,
+            0,
+            &uniform_bytes(billow, [-drift.x, -Y_OFFSET - drift.y, delta]),
+        );
+
+        let mut encoder = device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
+            label: Some("clouds"),
+        });
+        {
+            let mut pass = encoder.begin_compute_pass(&wgpu::ComputePassDescriptor {
+                label: Some("clouds"),
+            });
+            pass.set_pipeline(&self.pipeline);
+            pass.set_bind_group(0, &self.bind_group, &[]);
+            let groups = NUM_POINTS.div_ceil(WORKGROUP_SIZE);
+            pass.dispatch(groups, groups, 1);
+        }
+        encoder.copy_buffer_to_buffer(&self.output, 0, &self.readback, 0, self.output_size);
+        queue.submit(Some(encoder.finish()));
+
+        let slice = self.readback.slice(..);
+        let mapped = slice.map_async(wgpu::MapMode::Read);
+        device.poll(wgpu::Maintain::Wait);
+        futures::executor::block_on(mapped).expect("failed to read back cloud noise");
+
+        let mut points = [[0.; NUM_POINTS as usize]; NUM_POINTS as usize];
+        {
+            let bytes = slice.get_mapped_range();
+            let values = bytes
+                .chunks_exact(4)
+                .map(|b| f32::from_ne_bytes([b[0], b[1], b[2], b[3]]) as f64);
+            for (point, value) in points.iter_mut().flatten().zip(values) {
+                *point = value;
+            }
+        }
+        self.readback.unmap();
+        points
+    }
+}
+
+/// Packs the `Uniforms` block of `clouds.wgsl`. `origin` is the sample point
+/// of grid cell (0, 0).
+fn uniform_bytes(billow: &Billow, origin: [f64; 3]) -> Vec<u8> {
+    let mut bytes = Vec::with_capacity(UNIFORMS_SIZE as usize);
+    bytes.extend_from_slice(&(NUM_POINTS).to_ne_bytes());
+    bytes.extend_from_slice(&(billow.octaves as u32).to_ne_bytes());
+    bytes.extend_from_slice(&(billow.persistence as f32).to_ne_bytes());
+    bytes.extend_from_slice(&(billow.lacunarity as f32).to_ne_bytes());
+    let mut frequency = billow.frequency;
+    for octave in 0..MAX_OCTAVES {
+        for axis in origin {
+            let wrapped = if octave < billow.octaves {
+                (axis * frequency).rem_euclid(NOISE_PERIOD)
+            } else {
+                0.
+            };
+            bytes.extend_from_slice(&(wrapped as f32).to_ne_bytes());
+        }
+        bytes.extend_from_slice(&0f32.to_ne_bytes());
+        frequency *= billow.lacunarity;
+    }
+    bytes.extend_from_slice(&((billow.frequency / NOISE_SCALE) as f32).to_ne_bytes());
+    bytes.resize(UNIFORMS_SIZE as usize, 0);
+    bytes
+}
+
+/// The permutation table `noise` builds for a Perlin source with `seed`.
+/// Billow seeds each octave one higher than the last.
+fn permutation_table(seed: u32) -> Vec<u8> {
+    let mut key = [0; 16];
+    key[0] = 1;
+    for i in 1..4 {
+        key[i * 4..i * 4 + 4].copy_from_slice(&seed.to_le_bytes());
+    }
+    let mut rng = XorShiftRng::from_seed(key);
+    let mut table: Vec<u8> = (0..=255).collect();
+    table.shuffle(&mut rng);
+    table
+}
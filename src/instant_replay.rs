@@ -0,0 +1,166 @@
+use nannou::image::codecs::gif::{GifEncoder, Repeat};
+use nannou::image::{Delay, ImageResult, RgbaImage};
+use nannou::prelude::*;
+use std::cell::{Cell, RefCell};
+use std::collections::BTreeMap;
+use std::fs::File;
+use std::io::BufWriter;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+/// Frames kept per second of replay. 25 divides evenly into the GIF
+/// format's hundredths of a second.
+const REPLAY_FPS: u32 = 25;
+/// Width and height of the kept frames, half the window's.
+const REPLAY_SIZE: u32 = crate::SCREEN_SIZE / 2;
+/// Bytes one kept frame takes: 8-bit RGBA at `REPLAY_SIZE`.
+const REPLAY_FRAME_BYTES: usize = (REPLAY_SIZE * REPLAY_SIZE * 4) as usize;
+/// 1 is the slowest, best quantizer and 30 the fastest.
+const GIF_SPEED: i32 = 10;
+/// How long drawing waits for a readback worker to come free before the
+/// frame is dropped from the replay, a little under a frame at 60 fps.
+const READBACK_TIMEOUT: Duration = Duration::from_millis(12);
+
+/// The last few seconds of the sky, kept shrunk in memory so a moment can
+/// be saved with `K` after it has happened, as an animated GIF.
+///
+/// Each kept frame is copied off the GPU after the scene and its
+/// post-processing are drawn but before the HUD, scaled down to
+/// `REPLAY_SIZE` square. Frames are read back on worker threads and the
+/// GIF is encoded on another. When every worker is still busy, drawing
+/// waits at most `READBACK_TIMEOUT` before dropping that frame, which
+/// leaves a gap in the replay rather than a long stall.
+///
+/// Memory use is `REPLAY_FRAME_BYTES` (about 198 KiB) per frame at
+/// `REPLAY_FPS` frames a second, about 4.8 MiB per second of replay: 48 MiB
+/// for 10 seconds, 290 MiB for the longest allowed, a minute. Saving briefly
+/// holds a second copy while the GIF is encoded.
+pub struct InstantReplay {
+    capacity: usize,
+    texture: wgpu::Texture,
+    /// Draws the window's frame into `texture`, rebuilt if the frame's
+    /// texture changes, as it does when the window is resized.
+    reshaper: RefCell<Option<(wgpu::TextureViewId, wgpu::TextureReshaper)>>,
+    capturer: wgpu::TextureCapturer,
+    /// Kept frames by capture number, filled in as readbacks complete,
+    /// which may be out of order.
+    frames: Arc<Mutex<BTreeMap<u64, RgbaImage>>>,
+    captured: Cell<u64>,
+    /// Frames given up on because no readback worker came free in time.
+    dropped: Cell<u64>,
+    last_capture: Cell<Option<f32>>,
+}
+
+impl InstantReplay {
+    pub fn new(window: &Window, seconds: f32) -> Self {
+        let texture = wgpu::TextureBuilder::new()
+            .size([REPLAY_SIZE, REPLAY_SIZE])
+            .format(wgpu::TextureCapturer::DST_FORMAT)
+            .usage(wgpu::TextureUsages::RENDER_ATTACHMENT | wgpu::TextureUsages::COPY_SRC)
+            .build(window.device());
+        Self {
+            capacity: ((seconds * REPLAY_FPS as f32).ceil() as usize).max(1),
+            texture,
+            reshaper: RefCell::new(None),
+            capturer: wgpu::TextureCapturer::new(None, Some(READBACK_TIMEOUT)),
+            frames: Default::default(),
+            captured: Cell::new(0),
+            dropped: Cell::new(0),
+            last_capture: Cell::new(None),
+        }
+    }
+
+    /// Keeps a shrunk copy of what `frame` holds so far, unless a frame
+    /// was kept less than `1 / REPLAY_FPS` seconds before `time`.
+    pub fn capture(&self, window: &Window, frame: &Frame, time: f32) {
+        if let Some(last) = self.last_capture.get() {
+            if time - last < 1. / REPLAY_FPS as f32 {
+                return;
+            }
+        }
+        self.last_capture.set(Some(time));
+
+        let device = window.device();
+        // lets finished readbacks call back without waiting on the GPU
+        device.poll(wgpu::Maintain::Poll);
+
+        let source = frame.texture_view();
+        let mut reshaper = self.reshaper.borrow_mut();
+        if reshaper.as_ref().map(|(id, _)| *id) != Some(source.id()) {
+            let new = wgpu::TextureReshaper::new(
+                device,
+                source,
+                frame.texture_msaa_samples(),
+                source.sample_type(),
+                1,
+                wgpu::TextureCapturer::DST_FORMAT,
+            );
+            *reshaper = Some((source.id(), new));
+        }
+        let (_, reshaper) = reshaper.as_ref().unwrap();
+
+        let mut encoder = frame.command_encoder();
+        reshaper.encode_render_pass(&self.texture.view().build(), &mut encoder);
+        let snapshot = self.capturer.capture(device, &mut encoder, &self.texture);
+
+        let index = self.captured.get();
+        self.captured.set(index + 1);
+        let frames = self.frames.clone();
+        let capacity = self.capacity;
+        let read = snapshot.read(move |result| {
+            if let Ok(image) = result {
+                let mut frames = frames.lock().unwrap();
+                frames.insert(index, image.to_owned());
+                while frames.len() > capacity {
+                    frames.pop_first();
+                }
+            }
+        });
+        if read.is_err() {
+            self.dropped.set(self.dropped.get() + 1);
+        }
+    }
+
+    /// Frames left out of the replay because readback fell behind.
+    pub fn dropped(&self) -> u64 {
+        self.dropped.get()
+    }
+
+    /// Seconds of sky currently kept.
+    pub fn seconds(&self) -> f32 {
+        self.frames.lock().unwrap().len() as f32 / REPLAY_FPS as f32
+    }
+
+    /// Memory the kept frames take, in MiB.
+    pub fn memory_mib(&self) -> f32 {
+        let bytes = self.frames.lock().unwrap().len() * REPLAY_FRAME_BYTES;
+        bytes as f32 / (1024. * 1024.)
+    }
+
+    /// Writes the kept frames to `path` as a looping GIF, in the
+    /// background. Returns false if nothing has been kept yet.
+    pub fn save(&self, path: String) -> bool {
+        let frames: Vec<RgbaImage> = self.frames.lock().unwrap().values().cloned().collect();
+        if frames.is_empty() {
+            return false;
+        }
+        std::thread::spawn(move || {
+            let write = || -> ImageResult<()> {
+                let file = BufWriter::new(File::create(&path)?);
+                let mut encoder = GifEncoder::new_with_speed(file, GIF_SPEED);
+                encoder.set_repeat(Repeat::Infinite)?;
+                let delay = Delay::from_numer_denom_ms(1000, REPLAY_FPS);
+                encoder.encode_frames(
+                    frames
+                        .into_iter()
+                        .map(|image| nannou::image::Frame::from_parts(image, 0, 0, delay)),
+                )
+            };
+            match write() {
+                Ok(()) => eprintln!("saved replay {path}"),
+                Err(e) => eprintln!("replay {path}: {e}"),
+            }
+        });
+        true
+    }
+}
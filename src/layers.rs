@@ -0,0 +1,48 @@
+/// The separately drawn parts of the scene. `render` draws them back to
+/// front in the order given by `--layers`, which defaults to
+/// `DEFAULT_LAYERS`, and each can be switched off at runtime.
+#[derive(Clone, Copy, PartialEq)]
+pub enum Layer {
+    /// The blue wash towards the horizon.
+    Scattering,
+    MilkyWay,
+    Sun,
+    MoonGlow,
+    GreenFlash,
+    Stars,
+    /// The moon's disc and spots.
+    Moon,
+    Clouds,
+}
+
+pub const DEFAULT_LAYERS: [Layer; 8] = [
+    Layer::Scattering,
+    Layer::MilkyWay,
+    Layer::Sun,
+    Layer::MoonGlow,
+    Layer::GreenFlash,
+    Layer::Stars,
+    Layer::Moon,
+    Layer::Clouds,
+];
+
+impl Layer {
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Scattering => "scattering",
+            Self::MilkyWay => "milky-way",
+            Self::Sun => "sun",
+            Self::MoonGlow => "moon-glow",
+            Self::GreenFlash => "green-flash",
+            Self::Stars => "stars",
+            Self::Moon => "moon",
+            Self::Clouds => "clouds",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        DEFAULT_LAYERS
+            .into_iter()
+            .find(|layer| layer.name() == name)
+    }
+}
@@ -0,0 +1,106 @@
+use nannou::noise::{Billow, NoiseFn, Seedable};
+use nannou::prelude::*;
+
+use crate::scene::Scene;
+use crate::{rgb, with_alpha, Color, SCREEN_SIZE_F};
+
+/// Vertices along each side of the glow mesh.
+const GLOW_GRID: usize = 46;
+const GLOW_ALPHA: f32 = 0.16;
+/// Distance from the band's center line at which the glow has faded to
+/// about a third.
+const BAND_WIDTH: f32 = 55.;
+/// The band runs from the lower left towards the upper right, through this
+/// point.
+const BAND_CENTER: (f32, f32) = (SCREEN_SIZE_F / 2., SCREEN_SIZE_F * 0.55);
+const BAND_SLOPE: f32 = 0.6;
+/// Noise frequency along and across the band; the difference stretches the
+/// glow into streaks.
+const ALONG_SCALE: f64 = 0.008;
+const ACROSS_SCALE: f64 = 0.03;
+const NOISE_SEED: u32 = 7;
+const MICRO_STAR_COUNT: usize = 400;
+const MICRO_STAR_RADIUS: (f32, f32) = (0.5, 1.);
+const MILKY_WAY_COLOR: Srgb<u8> = rgb(205, 215, 255);
+
+/// The faint band of the galaxy across the night sky: a streaky glow plus a
+/// scattering of tiny stars that cluster along it. Both are laid out once at
+/// startup and only fade in and out afterwards.
+pub struct MilkyWay {
+    /// Glow strength at each vertex of a grid over the window, row by row.
+    glow: Vec<f32>,
+    /// Position, radius and brightness of each micro-star.
+    stars: Vec<(Point2, f32, f32)>,
+}
+
+impl MilkyWay {
+    pub fn new() -> Self {
+        let mut billow = Billow::new().set_seed(NOISE_SEED);
+        billow.octaves = 4;
+        let step = SCREEN_SIZE_F / (GLOW_GRID - 1) as f32;
+        let glow = (0..GLOW_GRID * GLOW_GRID)
+            .map(|i| density(&billow, grid_pos(i, step)))
+            .collect();
+
+        // rejection sample so the stars follow the glow
+        let mut stars = vec![];
+        while stars.len() < MICRO_STAR_COUNT {
+            let pos = pt2(random_f32(), random_f32()) * SCREEN_SIZE_F;
+            if random_f32() < density(&billow, pos) {
+                let (min, max) = MICRO_STAR_RADIUS;
+                stars.push((
+                    pos,
+                    min + random_f32() * (max - min),
+                    0.3 + random_f32() * 0.5,
+                ));
+            }
+        }
+        Self { glow, stars }
+    }
+
+    /// Draws the band at `visibility` (0 to 1), placing each point through
+    /// `project`.
+    pub fn draw(&self, scene: &mut Scene, visibility: f32, project: impl Fn(Point2) -> Point2) {
+        let step = SCREEN_SIZE_F / (GLOW_GRID - 1) as f32;
+        let vertices = self.glow.iter().enumerate().map(|(i, glow)| {
+            let color = with_alpha(
+                MILKY_WAY_COLOR.into(),
+                (glow * GLOW_ALPHA * visibility) as f64,
+            );
+            (project(grid_pos(i, step)).extend(0.), color)
+        });
+        let indices = (0..GLOW_GRID - 1).flat_map(|row| {
+            (0..GLOW_GRID - 1).flat_map(move |col| {
+                let a = row * GLOW_GRID + col;
+                let b = a + GLOW_GRID;
+                [a, a + 1, b + 1, a, b + 1, b]
+            })
+        });
+        scene
+            .draw()
+            .mesh()
+            .indexed_colored(vertices, indices)
+            .finish();
+
+        for &(pos, radius, brightness) in &self.stars {
+            let color: Color = with_alpha(WHITE.into(), (brightness * visibility) as f64);
+            scene.circles().disc(project(pos), radius, color);
+        }
+    }
+}
+
+fn grid_pos(i: usize, step: f32) -> Point2 {
+    pt2((i % GLOW_GRID) as f32, (i / GLOW_GRID) as f32) * step
+}
+
+/// Glow strength at `p`, 0 to 1: a soft band profile broken up by noise
+/// stretched along the band.
+fn density(billow: &Billow, p: Point2) -> f32 {
+    let along = vec2(1., BAND_SLOPE).normalize();
+    let rel = p - Vec2::from(BAND_CENTER);
+    let u = rel.dot(along);
+    let v = rel.dot(along.perp());
+    let profile = (-(v / BAND_WIDTH).powi(2)).exp();
+    let noise = billow.get([u as f64 * ALONG_SCALE, v as f64 * ACROSS_SCALE]) as f32;
+    profile * clamp(0.55 + 0.45 * noise, 0., 1.)
+}
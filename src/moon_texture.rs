@@ -0,0 +1,82 @@
+use nannou::rand::random_range;
+
+/// Shapes the noise that paints the moon's spots, see `Moon::texture`.
+#[derive(Clone, Copy)]
+pub struct MoonTextureParams {
+    pub seed: u32,
+    /// Billow persistence; higher values add more fine, crater-like detail.
+    pub persistence: f64,
+    /// Exponent applied to the noise, sharpening spots above 1.
+    pub exponent: f64,
+    /// Noise frequency per pixel across the disc. Lower values give larger
+    /// spots.
+    pub scale: f64,
+    /// Multiplier on the noise before thresholding.
+    pub contrast: f64,
+    /// Noise values below this leave the surface bare.
+    pub cutoff: f64,
+    /// Alpha of the darkest spots.
+    pub max_alpha: f64,
+}
+
+impl Default for MoonTextureParams {
+    fn default() -> Self {
+        Self {
+            seed: 0,
+            persistence: 0.15,
+            exponent: 1.,
+            scale: 0.5,
+            contrast: 0.75,
+            cutoff: 0.2,
+            max_alpha: 0.85,
+        }
+    }
+}
+
+impl MoonTextureParams {
+    pub fn from_name(name: &str) -> Option<Self> {
+        let default = Self::default();
+        match name {
+            "default" => Some(default),
+            "smooth" => Some(Self {
+                persistence: 0.1,
+                cutoff: 0.4,
+                max_alpha: 0.5,
+                ..default
+            }),
+            "cratered" => Some(Self {
+                persistence: 0.5,
+                scale: 1.,
+                contrast: 1.,
+                cutoff: 0.25,
+                max_alpha: 0.95,
+                ..default
+            }),
+            // broad soft blotches, like a gas giant
+            "mottled" => Some(Self {
+                persistence: 0.3,
+                exponent: 2.,
+                scale: 0.2,
+                contrast: 1.,
+                cutoff: 0.,
+                max_alpha: 0.6,
+                ..default
+            }),
+            _ => None,
+        }
+    }
+
+    /// A random variation, for previewing looks with `R`.
+    pub fn random() -> Self {
+        let cutoff = random_range(0., 0.5);
+        Self {
+            seed: random_range(0, u32::MAX),
+            persistence: random_range(0.05, 0.6),
+            exponent: random_range(0.5, 2.5),
+            scale: random_range(0.15, 1.2),
+            contrast: random_range(0.5, 1.),
+            cutoff,
+            max_alpha: random_range(0.4, 1.),
+        }
+    }
+}
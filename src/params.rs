@@ -0,0 +1,234 @@
+use nannou::prelude::*;
+use std::ops::RangeInclusive;
+
+use crate::{lerp_rgb, rgb, MAX_STAR_COUNT, MAX_WIND, NIGHT_SKY_COLOR, SUNSET_SKY_COLOR};
+
+pub const DEFAULT_CLOUD_DENSITY: f32 = 0.5;
+pub const DEFAULT_STAR_COUNT: f32 = 30.;
+/// Stars hold steady unless twinkle is asked for with `]` or a preset.
+pub const DEFAULT_TURBULENCE: f32 = 0.;
+
+/// The three sky colors the day cycle moves between.
+#[derive(Clone, Copy)]
+pub struct Palette {
+    pub day: Srgb<u8>,
+    pub sunset: Srgb<u8>,
+    pub night: Srgb<u8>,
+}
+
+impl Palette {
+    fn lerp(&self, other: &Self, t: f32) -> Self {
+        Self {
+            day: ease_rgb(self.day, other.day, t),
+            sunset: ease_rgb(self.sunset, other.sunset, t),
+            night: ease_rgb(self.night, other.night, t),
+        }
+    }
+}
+
+/// `lerp_rgb` for easing a little way each frame. Channels are whole
+/// numbers, so once a small `t` of the remaining gap rounds to nothing the
+/// channel steps one level instead, and still reaches `to`.
+fn ease_rgb(from: Srgb<u8>, to: Srgb<u8>, t: f32) -> Srgb<u8> {
+    let eased = lerp_rgb(from, to, t);
+    if t <= 0. {
+        return eased;
+    }
+    let step = |from: u8, eased: u8, to: u8| {
+        if eased != from || from == to {
+            eased
+        } else if to > from {
+            from + 1
+        } else {
+            from - 1
+        }
+    };
+    Srgb::new(
+        step(from.red, eased.red, to.red),
+        step(from.green, eased.green, to.green),
+        step(from.blue, eased.blue, to.blue),
+    )
+}
+
+/// The runtime-tunable look of the sky. `Model` eases its current params
+/// towards a target, so changing them never snaps.
+#[derive(Clone, Copy)]
+pub struct Params {
+    pub palette: Palette,
+    /// 0 is a clear sky, 1 is fully overcast.
+    pub cloud_density: f32,
+    /// How many stars are visible at night; fractional counts fade the last star in.
+    pub star_count: f32,
+    /// Twinkle strength, scaled up towards the horizon.
+    pub turbulence: f32,
+    /// How dark and heavy clouds look, 0 being fair weather.
+    pub storminess: f32,
+    /// How strongly large-scale coverage noise clumps clouds into banks.
+    /// 0 leaves the fine detail evenly scattered.
+    pub coverage: f32,
+    /// Wind speed as a multiple of the default cloud drift.
+    pub wind: f32,
+}
+
+impl Default for Params {
+    fn default() -> Self {
+        PRESETS[0].params
+    }
+}
+
+impl Params {
+    pub fn lerp(&self, other: &Self, t: f32) -> Self {
+        Self {
+            palette: self.palette.lerp(&other.palette, t),
+            cloud_density: lerp(self.cloud_density, other.cloud_density, t),
+            star_count: lerp(self.star_count, other.star_count, t),
+            turbulence: lerp(self.turbulence, other.turbulence, t),
+            storminess: lerp(self.storminess, other.storminess, t),
+            coverage: lerp(self.coverage, other.coverage, t),
+            wind: lerp(self.wind, other.wind, t),
+        }
+    }
+
+    pub fn get_mut(&mut self, kind: ParamKind) -> &mut f32 {
+        match kind {
+            ParamKind::CloudDensity => &mut self.cloud_density,
+            ParamKind::StarCount => &mut self.star_count,
+            ParamKind::Turbulence => &mut self.turbulence,
+            ParamKind::Storminess => &mut self.storminess,
+            ParamKind::Coverage => &mut self.coverage,
+            ParamKind::Wind => &mut self.wind,
+        }
+    }
+}
+
+/// The scalar fields of `Params`, for setting them by name.
+#[derive(Clone, Copy)]
+pub enum ParamKind {
+    CloudDensity,
+    StarCount,
+    Turbulence,
+    Storminess,
+    Coverage,
+    Wind,
+}
+
+impl ParamKind {
+    pub fn from_name(name: &str) -> Option<Self> {
+        Some(match name {
+            "density" => Self::CloudDensity,
+            "stars" => Self::StarCount,
+            "turbulence" => Self::Turbulence,
+            "storminess" => Self::Storminess,
+            "coverage" => Self::Coverage,
+            "wind" => Self::Wind,
+            _ => return None,
+        })
+    }
+
+    /// The values the parameter can take without breaking the drawing.
+    pub fn range(self) -> RangeInclusive<f32> {
+        match self {
+            Self::CloudDensity | Self::Turbulence | Self::Storminess | Self::Coverage => 0. ..=1.,
+            Self::StarCount => 0. ..=MAX_STAR_COUNT as f32,
+            Self::Wind => 0. ..=MAX_WIND,
+        }
+    }
+}
+
+pub struct Preset {
+    pub name: &'static str,
+    pub params: Params,
+}
+
+pub const PRESETS: [Preset; 4] = [
+    Preset {
+        name: "Classic",
+        params: Params {
+            palette: Palette {
+                day: LIGHTSKYBLUE,
+                sunset: SUNSET_SKY_COLOR,
+                night: NIGHT_SKY_COLOR,
+            },
+            cloud_density: DEFAULT_CLOUD_DENSITY,
+            star_count: DEFAULT_STAR_COUNT,
+            turbulence: DEFAULT_TURBULENCE,
+            storminess: 0.,
+            coverage: 0.,
+            wind: 1.,
+        },
+    },
+    Preset {
+        name: "Clear Dawn",
+        params: Params {
+            palette: Palette {
+                day: rgb(160, 205, 240),
+                sunset: rgb(250, 160, 150),
+                night: rgb(30, 35, 60),
+            },
+            cloud_density: 0.25,
+            star_count: 40.,
+            turbulence: 0.3,
+            storminess: 0.,
+            coverage: 0.5,
+            wind: 0.8,
+        },
+    },
+    Preset {
+        name: "Stormy Dusk",
+        params: Params {
+            palette: Palette {
+                day: rgb(120, 140, 160),
+                sunset: rgb(200, 90, 40),
+                night: rgb(15, 18, 24),
+            },
+            cloud_density: 0.8,
+            star_count: 10.,
+            turbulence: 0.7,
+            storminess: 0.7,
+            coverage: 0.6,
+            wind: 4.,
+        },
+    },
+    Preset {
+        name: "Starry Desert Night",
+        params: Params {
+            palette: Palette {
+                day: rgb(110, 180, 235),
+                sunset: rgb(255, 140, 60),
+                night: rgb(8, 10, 22),
+            },
+            cloud_density: 0.1,
+            star_count: 120.,
+            turbulence: 0.2,
+            storminess: 0.,
+            coverage: 0.3,
+            wind: 0.5,
+        },
+    },
+];
+
+fn lerp(a: f32, b: f32, t: f32) -> f32 {
+    a + (b - a) * t
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn palette_easing_reaches_its_target() {
+        let from = PRESETS[0].params.palette;
+        let to = PRESETS[1].params.palette;
+        let mut palette = from;
+        for _ in 0..10_000 {
+            palette = palette.lerp(&to, crate::PARAM_EASE_RATE);
+        }
+        for (eased, target) in [
+            (palette.day, to.day),
+            (palette.sunset, to.sunset),
+            (palette.night, to.night),
+        ] {
+            assert_eq!(eased, target);
+        }
+    }
+}
@@ -0,0 +1,258 @@
+use nannou::prelude::*;
+use nannou::wgpu::util::DeviceExt;
+use std::cell::RefCell;
+
+use crate::circles::CircleRenderer;
+use crate::scene::{Pass, Scene};
+
+/// Tunables for the full-screen post-processing pass.
+#[derive(Clone, Copy)]
+pub struct PostSettings {
+    /// How much of the blurred highlights is added back; 0 disables bloom.
+    pub bloom_strength: f32,
+    /// Luminance above which a pixel starts to glow.
+    pub bloom_threshold: f32,
+    /// How far the image is blended towards its tone-mapped version; 0
+    /// disables tone mapping.
+    pub tone_map_strength: f32,
+    pub tone_curve: ToneCurve,
+    /// Multiplier on the scene's brightness, applied after bloom and before
+    /// tone mapping; 1 leaves it unchanged.
+    pub exposure: f32,
+    /// Final grading, applied after tone mapping.
+    pub color_curves: ColorCurves,
+}
+
+/// Curve that compresses highlights into displayable range. Both roll bright
+/// values off towards white instead of clipping; ACES also adds a little
+/// contrast in the shadows.
+#[derive(Clone, Copy, PartialEq)]
+pub enum ToneCurve {
+    Reinhard,
+    Aces,
+}
+
+/// A curve for one color channel: the channel is raised to `1 / gamma`,
+/// then rescaled so black lands on `lift` and white on `gain`.
+#[derive(Clone, Copy, PartialEq)]
+pub struct ChannelCurve {
+    pub lift: f32,
+    pub gamma: f32,
+    pub gain: f32,
+}
+
+impl ChannelCurve {
+    pub const IDENTITY: Self = Self {
+        lift: 0.,
+        gamma: 1.,
+        gain: 1.,
+    };
+}
+
+/// Per-channel grading curves, applied after tone mapping, so they shape
+/// the already compressed image rather than the raw highlights.
+#[derive(Clone, Copy, PartialEq)]
+pub struct ColorCurves {
+    pub red: ChannelCurve,
+    pub green: ChannelCurve,
+    pub blue: ChannelCurve,
+}
+
+impl ColorCurves {
+    pub const IDENTITY: Self = Self {
+        red: ChannelCurve::IDENTITY,
+        green: ChannelCurve::IDENTITY,
+        blue: ChannelCurve::IDENTITY,
+    };
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        let curve = |lift, gamma, gain| ChannelCurve { lift, gamma, gain };
+        match name {
+            "identity" => Some(Self::IDENTITY),
+            // warm and faded: blues crushed, shadows lifted towards amber
+            "vintage" => Some(Self {
+                red: curve(0.06, 1.05, 1.),
+                green: curve(0.04, 1., 0.95),
+                blue: curve(0.02, 0.85, 0.8),
+            }),
+            // sickly green cast in the midtones
+            "eerie" => Some(Self {
+                red: curve(0., 0.9, 0.92),
+                green: curve(0.05, 1.25, 1.),
+                blue: curve(0.02, 0.95, 0.9),
+            }),
+            // cold moonlit look
+            "cool" => Some(Self {
+                red: curve(0., 0.9, 0.9),
+                green: curve(0., 1., 0.97),
+                blue: curve(0.04, 1.15, 1.),
+            }),
+            _ => None,
+        }
+    }
+
+    fn as_bytes(&self) -> Vec<u8> {
+        [self.red, self.green, self.blue]
+            .iter()
+            .flat_map(|c| [c.lift, c.gamma, c.gain, 0.])
+            .flat_map(|f| f.to_ne_bytes())
+            .collect()
+    }
+}
+
+impl ToneCurve {
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "reinhard" => Some(Self::Reinhard),
+            "aces" => Some(Self::Aces),
+            _ => None,
+        }
+    }
+}
+
+impl PostSettings {
+    /// Whether the pass would change the image at all. When it wouldn't the
+    /// scene is drawn straight to the frame and the pass is skipped.
+    pub fn is_active(&self) -> bool {
+        self.bloom_strength > 0.
+            || self.tone_map_strength > 0.
+            || self.exposure != 1.
+            || self.color_curves != ColorCurves::IDENTITY
+    }
+
+    fn as_bytes(&self, texel: [f32; 2]) -> Vec<u8> {
+        let curve = match self.tone_curve {
+            ToneCurve::Reinhard => 0u32,
+            ToneCurve::Aces => 1,
+        };
+        let mut bytes: Vec<u8> = [
+            texel[0],
+            texel[1],
+            self.bloom_strength,
+            self.bloom_threshold,
+            self.tone_map_strength,
+        ]
+        .iter()
+        .flat_map(|f| f.to_ne_bytes())
+        .collect();
+        bytes.extend_from_slice(&curve.to_ne_bytes());
+        bytes.extend_from_slice(&self.exposure.to_ne_bytes());
+        // the curves are vec4s, aligned to 16 bytes
+        bytes.resize(32, 0);
+        bytes.extend(self.color_curves.as_bytes());
+        bytes
+    }
+}
+
+/// Renders the scene into an offscreen texture, then draws it to the frame
+/// through `post.wgsl`.
+///
+/// Tone mapping alone costs a window-sized `Frame::TEXTURE_FORMAT` render
+/// target and one texture tap per pixel; bloom adds 49 more taps per pixel
+/// (a center tap and four rings of 12). The frame never leaves the GPU, and
+/// neither is paid while its settings are off.
+pub struct PostProcess {
+    scene: wgpu::Texture,
+    scene_view: wgpu::TextureView,
+    renderer: RefCell<nannou::draw::Renderer>,
+    uniforms: wgpu::Buffer,
+    bind_group: wgpu::BindGroup,
+    pipeline: wgpu::RenderPipeline,
+}
+
+impl PostProcess {
+    pub fn new(window: &Window, size: [u32; 2]) -> Self {
+        let device = window.device();
+        let scene = wgpu::TextureBuilder::new()
+            .size(size)
+            .format(Frame::TEXTURE_FORMAT)
+            .usage(wgpu::TextureUsages::RENDER_ATTACHMENT | wgpu::TextureUsages::TEXTURE_BINDING)
+            .build(device);
+        let renderer = nannou::draw::RendererBuilder::new()
+            .build_from_texture_descriptor(device, scene.descriptor());
+        let sampler = wgpu::SamplerBuilder::new().build(device);
+        let uniforms = device.create_buffer_init(&wgpu::BufferInitDescriptor {
+            label: Some("post uniforms"),
+            contents: &PostSettings {
+                bloom_strength: 0.,
+                bloom_threshold: 0.,
+                tone_map_strength: 0.,
+                tone_curve: ToneCurve::Reinhard,
+                exposure: 1.,
+                color_curves: ColorCurves::IDENTITY,
+            }
+            .as_bytes([0., 0.]),
+            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+        });
+
+        let layout = wgpu::BindGroupLayoutBuilder::new()
+            .texture_from(wgpu::ShaderStages::FRAGMENT, &scene)
+            .sampler(wgpu::ShaderStages::FRAGMENT, true)
+            .uniform_buffer(wgpu::ShaderStages::FRAGMENT, false)
+            .build(device);
+        let scene_view = scene.view().build();
+        let bind_group = wgpu::BindGroupBuilder::new()
+            .texture_view(&scene_view)
+            .sampler(&sampler)
+            .buffer_bytes(&uniforms, 0, None)
+            .build(device, &layout);
+        let pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("post"),
+            bind_group_layouts: &[&layout],
+            push_constant_ranges: &[],
+        });
+        let shader = device.create_shader_module(&wgpu::include_wgsl!("post.wgsl"));
+        let pipeline = wgpu::RenderPipelineBuilder::from_layout(&pipeline_layout, &shader)
+            .vertex_entry_point("vs_main")
+            .fragment_shader(&shader)
+            .fragment_entry_point("fs_main")
+            .color_format(Frame::TEXTURE_FORMAT)
+            .sample_count(window.msaa_samples())
+            .build(device);
+
+        Self {
+            scene,
+            scene_view,
+            renderer: RefCell::new(renderer),
+            uniforms,
+            bind_group,
+            pipeline,
+        }
+    }
+
+    pub fn render(
+        &self,
+        window: &Window,
+        scene: &Scene,
+        circles: &CircleRenderer,
+        frame: &Frame,
+        settings: PostSettings,
+    ) {
+        let device = window.device();
+        let [w, h] = self.scene.size();
+        window.queue().write_buffer(
+            &self.uniforms,
+            0,
+            &settings.as_bytes([1. / w as f32, 1. / h as f32]),
+        );
+
+        let mut encoder = frame.command_encoder();
+        for pass in scene.prepare(window, circles, vec2(w as f32, h as f32)) {
+            match pass {
+                Pass::Draw(draw) => self.renderer.borrow_mut().render_to_texture(
+                    device,
+                    &mut encoder,
+                    draw,
+                    &self.scene,
+                ),
+                Pass::Circles(range) => circles.encode(&mut encoder, &self.scene_view, 1, range),
+            }
+        }
+        let mut pass = wgpu::RenderPassBuilder::new()
+            .color_attachment(frame.texture_view(), |color| color)
+            .begin(&mut encoder);
+        pass.set_pipeline(&self.pipeline);
+        pass.set_bind_group(0, &self.bind_group, &[]);
+        pass.draw(0..3, 0..1);
+    }
+}
@@ -0,0 +1,113 @@
+// Full-screen post-processing pass over the rendered scene.
+
+[[block]]
+struct Uniforms {
+    texel: vec2<f32>;
+    bloom_strength: f32;
+    bloom_threshold: f32;
+    tone_map_strength: f32;
+    // 0 for Reinhard, 1 for ACES.
+    tone_curve: u32;
+    // Multiplier on the whole scene, from the sun's altitude.
+    exposure: f32;
+    // Per-channel lift, gamma and gain.
+    red_curve: vec4<f32>;
+    green_curve: vec4<f32>;
+    blue_curve: vec4<f32>;
+};
+
+struct VertexOutput {
+    [[builtin(position)]] position: vec4<f32>;
+    [[location(0)]] uv: vec2<f32>;
+};
+
+[[group(0), binding(0)]] var scene: texture_2d<f32>;
+[[group(0), binding(1)]] var scene_sampler: sampler;
+[[group(0), binding(2)]] var<uniform> uniforms: Uniforms;
+
+// A single triangle covering the whole viewport, no vertex buffer needed.
+[[stage(vertex)]]
+fn vs_main([[builtin(vertex_index)]] index: u32) -> VertexOutput {
+    let corner = vec2<f32>(f32((index << 1u) & 2u), f32(index & 2u));
+    var out: VertexOutput;
+    out.position = vec4<f32>(corner * 2.0 - 1.0, 0.0, 1.0);
+    out.uv = vec2<f32>(corner.x, 1.0 - corner.y);
+    return out;
+}
+
+fn luminance(color: vec3<f32>) -> f32 {
+    return dot(color, vec3<f32>(0.2126, 0.7152, 0.0722));
+}
+
+// The part of a sample brighter than the bloom threshold.
+fn bright_pass(uv: vec2<f32>) -> vec3<f32> {
+    let color = textureSample(scene, scene_sampler, uv).rgb;
+    let l = luminance(color);
+    return color * max(l - uniforms.bloom_threshold, 0.0) / max(l, 0.0001);
+}
+
+// Blurs the bright parts of the scene with rings of taps around the pixel.
+fn bloom(uv: vec2<f32>) -> vec3<f32> {
+    var sum = bright_pass(uv);
+    var weight = 1.0;
+    for (var ring: i32 = 1; ring <= 4; ring = ring + 1) {
+        let radius = f32(ring * ring) * 2.0;
+        let ring_weight = exp(-f32(ring * ring) / 6.0);
+        for (var tap: i32 = 0; tap < 12; tap = tap + 1) {
+            let angle = f32(tap) * 0.5235988 + f32(ring) * 0.2617994;
+            let offset = vec2<f32>(cos(angle), sin(angle)) * radius * uniforms.texel;
+            sum = sum + bright_pass(uv + offset) * ring_weight;
+            weight = weight + ring_weight;
+        }
+    }
+    return sum / weight;
+}
+
+// Extended Reinhard, with a white point a little above 1 so the bloom's
+// overshoot still reaches full white.
+fn reinhard(color: vec3<f32>) -> vec3<f32> {
+    let white = 1.5;
+    return color * (1.0 + color / (white * white)) / (1.0 + color);
+}
+
+// Narkowicz's fit of the ACES filmic curve.
+fn aces(color: vec3<f32>) -> vec3<f32> {
+    let a = color * (2.51 * color + 0.03);
+    let b = color * (2.43 * color + 0.59) + 0.14;
+    return clamp(a / b, vec3<f32>(0.0), vec3<f32>(1.0));
+}
+
+fn channel_curve(value: f32, curve: vec4<f32>) -> f32 {
+    let shaped = pow(clamp(value, 0.0, 1.0), 1.0 / curve.y);
+    return curve.x + (curve.z - curve.x) * shaped;
+}
+
+[[stage(fragment)]]
+fn fs_main(in: VertexOutput) -> [[location(0)]] vec4<f32> {
+    let scene_color = textureSample(scene, scene_sampler, in.uv);
+    var color = scene_color.rgb;
+    var alpha = scene_color.a;
+    if (uniforms.bloom_strength > 0.0) {
+        let glow = bloom(in.uv) * uniforms.bloom_strength;
+        color = color + glow;
+        alpha = clamp(alpha + luminance(glow), 0.0, 1.0);
+    }
+    // before tone mapping, so a bright midday rolls off rather than clips
+    color = color * uniforms.exposure;
+    if (uniforms.tone_map_strength > 0.0) {
+        var mapped: vec3<f32>;
+        if (uniforms.tone_curve == 0u) {
+            mapped = reinhard(color);
+        } else {
+            mapped = aces(color);
+        }
+        color = mix(color, mapped, uniforms.tone_map_strength);
+    }
+    // graded after tone mapping, on display-range values
+    color = vec3<f32>(
+        channel_curve(color.r, uniforms.red_curve),
+        channel_curve(color.g, uniforms.green_curve),
+        channel_curve(color.b, uniforms.blue_curve),
+    );
+    return vec4<f32>(color, alpha);
+}
@@ -0,0 +1,117 @@
+use std::cell::Cell;
+use std::time::{Duration, Instant};
+
+const SUMMARY_INTERVAL: Duration = Duration::from_secs(5);
+/// Over-budget frames are still counted in the summary, but warned about
+/// at most this often.
+const WARNING_INTERVAL: Duration = Duration::from_secs(1);
+
+/// The parts of a frame that are timed.
+#[derive(Clone, Copy)]
+pub enum Phase {
+    /// Sampling and thresholding the cloud grid, including the loop blend.
+    Clouds,
+    /// Counting the cloud cover over the sun to darken the sky.
+    SunCoverage,
+    /// Building the draw list and submitting it in `view`. GPU time isn't
+    /// included.
+    Render,
+}
+
+const PHASES: [(Phase, &str); 3] = [
+    (Phase::Clouds, "clouds"),
+    (Phase::SunCoverage, "sun coverage"),
+    (Phase::Render, "render"),
+];
+
+/// Lightweight always-on timing of each frame's phases, enabled with
+/// `--profile`. Warns when a frame goes over budget, naming the slowest
+/// phase, and prints a summary every few seconds.
+///
+/// Recording takes `&self` so `view`, which only sees the model immutably,
+/// can time itself.
+pub struct Profiler {
+    budget: Duration,
+    frame: [Cell<Duration>; 3],
+    totals: [Duration; 3],
+    frames: u32,
+    over_budget: u32,
+    summary_at: Instant,
+    warned_at: Option<Instant>,
+}
+
+impl Profiler {
+    pub fn new(budget: Duration) -> Self {
+        Self {
+            budget,
+            frame: Default::default(),
+            totals: Default::default(),
+            frames: 0,
+            over_budget: 0,
+            summary_at: Instant::now(),
+            warned_at: None,
+        }
+    }
+
+    pub fn record(&self, phase: Phase, start: Instant) {
+        let slot = &self.frame[phase as usize];
+        slot.set(slot.get() + start.elapsed());
+    }
+
+    /// Closes out the previous frame's timings. Called at the top of
+    /// `update`, once the previous frame's `view` has run.
+    pub fn end_frame(&mut self) {
+        let times = self.frame.each_ref().map(|slot| slot.take());
+        let total: Duration = times.iter().sum();
+        for (sum, time) in self.totals.iter_mut().zip(times) {
+            *sum += time;
+        }
+        self.frames += 1;
+
+        if total > self.budget {
+            self.over_budget += 1;
+            let (slowest, _) = PHASES
+                .iter()
+                .zip(times)
+                .max_by_key(|(_, time)| *time)
+                .unwrap();
+            let now = Instant::now();
+            if self.warned_at.is_none_or(|at| now - at >= WARNING_INTERVAL) {
+                self.warned_at = Some(now);
+                eprintln!(
+                    "frame took {:.1}ms, over the {:.1}ms budget; slowest phase: {} {:.1}ms",
+                    ms(total),
+                    ms(self.budget),
+                    slowest.1,
+                    ms(times[slowest.0 as usize]),
+                );
+            }
+        }
+
+        if self.summary_at.elapsed() >= SUMMARY_INTERVAL {
+            let phases: Vec<_> = PHASES
+                .iter()
+                .map(|(phase, name)| {
+                    format!(
+                        "{name} {:.2}ms",
+                        ms(self.totals[*phase as usize]) / self.frames as f64
+                    )
+                })
+                .collect();
+            eprintln!(
+                "profile over {} frames: {}; {} over budget",
+                self.frames,
+                phases.join(", "),
+                self.over_budget
+            );
+            self.totals = Default::default();
+            self.frames = 0;
+            self.over_budget = 0;
+            self.summary_at = Instant::now();
+        }
+    }
+}
+
+fn ms(duration: Duration) -> f64 {
+    duration.as_secs_f64() * 1000.
+}
@@ -0,0 +1,104 @@
+use nannou::prelude::*;
+use std::ops::Range;
+
+use crate::batch::CircleBatch;
+use crate::circles::CircleRenderer;
+use crate::SCREEN_SIZE_F;
+
+/// One frame's drawing in scene coordinates, split into runs of ordinary
+/// nannou shapes and runs of circles for `CircleRenderer`. The runs are
+/// kept in the order they were drawn, so a layer of circles still covers
+/// the meshes drawn before it and is covered by those after.
+pub struct Scene {
+    zoom: f32,
+    segments: Vec<Segment>,
+}
+
+enum Segment {
+    Draw(Draw),
+    Circles(CircleBatch),
+}
+
+/// A segment ready to render, its circles already uploaded.
+pub enum Pass<'a> {
+    Draw(&'a Draw),
+    Circles(Range<u32>),
+}
+
+impl Scene {
+    /// An empty scene magnified `zoom` times about the window's center.
+    pub fn new(zoom: f32) -> Self {
+        Self {
+            zoom,
+            segments: vec![],
+        }
+    }
+
+    /// Where to draw everything that isn't a circle.
+    pub fn draw(&mut self) -> &Draw {
+        if !matches!(self.segments.last(), Some(Segment::Draw(_))) {
+            let draw = Draw::new()
+                .scale(self.zoom)
+                .x_y(-SCREEN_SIZE_F / 2., -SCREEN_SIZE_F / 2.);
+            self.segments.push(Segment::Draw(draw));
+        }
+        match self.segments.last() {
+            Some(Segment::Draw(draw)) => draw,
+            _ => unreachable!(),
+        }
+    }
+
+    /// Where to add circles, on top of everything drawn so far.
+    pub fn circles(&mut self) -> &mut CircleBatch {
+        if !matches!(self.segments.last(), Some(Segment::Circles(_))) {
+            self.segments.push(Segment::Circles(CircleBatch::default()));
+        }
+        match self.segments.last_mut() {
+            Some(Segment::Circles(batch)) => batch,
+            _ => unreachable!(),
+        }
+    }
+
+    /// Uploads the circles for a target `viewport` points across and
+    /// returns the passes to render, in order.
+    pub fn prepare(
+        &self,
+        window: &Window,
+        circles: &CircleRenderer,
+        viewport: Vec2,
+    ) -> Vec<Pass<'_>> {
+        let mut instances = vec![];
+        let mut passes = vec![];
+        let mut start = 0;
+        for segment in &self.segments {
+            match segment {
+                Segment::Draw(draw) => passes.push(Pass::Draw(draw)),
+                Segment::Circles(batch) if batch.is_empty() => {}
+                Segment::Circles(batch) => {
+                    instances.extend_from_slice(batch.as_bytes());
+                    let end = start + batch.len() as u32;
+                    passes.push(Pass::Circles(start..end));
+                    start = end;
+                }
+            }
+        }
+        let origin = pt2(SCREEN_SIZE_F / 2., SCREEN_SIZE_F / 2.);
+        circles.prepare(window, &instances, viewport, self.zoom, origin);
+        passes
+    }
+
+    pub fn to_frame(&self, app: &App, circles: &CircleRenderer, frame: &Frame) {
+        let window = app.main_window();
+        for pass in self.prepare(&window, circles, frame.rect().wh()) {
+            match pass {
+                Pass::Draw(draw) => draw.to_frame(app, frame).unwrap(),
+                Pass::Circles(range) => circles.encode(
+                    &mut frame.command_encoder(),
+                    frame.texture_view(),
+                    frame.texture_msaa_samples(),
+                    range,
+                ),
+            }
+        }
+    }
+}
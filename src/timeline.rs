@@ -0,0 +1,168 @@
+use std::fmt;
+
+use crate::params::{ParamKind, PRESETS};
+
+/// Named points in the day cycle, in normalized time.
+const NAMED_TIMES: [(&str, f64); 4] = [
+    ("noon", 0.),
+    ("sunset", 0.12),
+    ("midnight", 0.5),
+    ("sunrise", 0.87),
+];
+
+#[derive(Clone, Copy)]
+pub enum Action {
+    /// Move the day cycle to a normalized time.
+    Jump(f64),
+    /// Ease into the preset at this index.
+    Preset(usize),
+    /// Ease a single parameter towards a value.
+    Set(ParamKind, f32),
+    /// Restore the default parameters and restart the day cycle.
+    Reset,
+}
+
+/// A scripted sequence of actions fired as wall-clock time passes.
+///
+/// Scripts have one entry per line: a time in seconds (an `s` suffix is
+/// allowed), an action and its arguments. Blank lines and lines starting
+/// with `#` are ignored.
+///
+/// ```text
+/// 10s jump sunset
+/// 15s preset Stormy Dusk
+/// 20s set wind 4
+/// 30s reset
+/// ```
+pub struct Timeline {
+    entries: Vec<(f32, Action)>,
+    next: usize,
+}
+
+#[derive(Debug)]
+pub struct TimelineError {
+    line: usize,
+    message: String,
+}
+
+impl fmt::Display for TimelineError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "timeline line {}: {}", self.line, self.message)
+    }
+}
+
+impl Timeline {
+    pub fn parse(script: &str) -> Result<Self, TimelineError> {
+        let mut entries = vec![];
+        for (index, line) in script.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let error = |message: String| TimelineError {
+                line: index + 1,
+                message,
+            };
+            let (time, rest) = line.split_once(char::is_whitespace).unwrap_or((line, ""));
+            let time: f32 = time
+                .strip_suffix('s')
+                .unwrap_or(time)
+                .parse()
+                .map_err(|_| error(format!("invalid time `{time}`")))?;
+            let action = parse_action(rest.trim()).map_err(error)?;
+            entries.push((time, action));
+        }
+        entries.sort_by(|a, b| a.0.total_cmp(&b.0));
+        Ok(Self { entries, next: 0 })
+    }
+
+    /// Actions whose time has come since the last call.
+    pub fn due(&mut self, now: f32) -> Vec<Action> {
+        let start = self.next;
+        while self.next < self.entries.len() && self.entries[self.next].0 <= now {
+            self.next += 1;
+        }
+        self.entries[start..self.next]
+            .iter()
+            .map(|(_, action)| *action)
+            .collect()
+    }
+}
+
+fn parse_action(action: &str) -> Result<Action, String> {
+    let (name, args) = action
+        .split_once(char::is_whitespace)
+        .unwrap_or((action, ""));
+    let args = args.trim();
+    match name {
+        "jump" => NAMED_TIMES
+            .iter()
+            .find(|(name, _)| *name == args)
+            .map(|(_, time)| *time)
+            .or_else(|| args.parse().ok().filter(|t| (0. ..=1.).contains(t)))
+            .map(Action::Jump)
+            .ok_or_else(|| {
+                format!("jump expects noon, sunset, midnight, sunrise or 0-1, got `{args}`")
+            }),
+        "preset" => PRESETS
+            .iter()
+            .position(|preset| preset.name.eq_ignore_ascii_case(args))
+            .map(Action::Preset)
+            .ok_or_else(|| format!("unknown preset `{args}`")),
+        "set" => {
+            let (param_name, value) = args
+                .split_once(char::is_whitespace)
+                .ok_or_else(|| format!("set expects a parameter and a value, got `{args}`"))?;
+            let param = ParamKind::from_name(param_name)
+                .ok_or_else(|| format!("unknown parameter `{param_name}`"))?;
+            let value: f32 = value
+                .trim()
+                .parse()
+                .map_err(|_| format!("invalid value `{}`", value.trim()))?;
+            let range = param.range();
+            if !value.is_finite() || !range.contains(&value) {
+                return Err(format!(
+                    "{param_name} expects a value between {} and {}, got {value}",
+                    range.start(),
+                    range.end()
+                ));
+            }
+            Ok(Action::Set(param, value))
+        }
+        "reset" if args.is_empty() => Ok(Action::Reset),
+        _ => Err(format!("unknown action `{action}`")),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn set_rejects_values_out_of_range() {
+        for line in [
+            "1 set coverage 3",
+            "1 set density -0.1",
+            "1 set density nan",
+            "1 set turbulence inf",
+            "1 set storminess 1.5",
+            "1 set wind 100",
+            "1 set wind -inf",
+            "1 set stars 1000",
+        ] {
+            assert!(Timeline::parse(line).is_err(), "accepted `{line}`");
+        }
+    }
+
+    #[test]
+    fn set_accepts_values_in_range() {
+        for line in [
+            "1 set coverage 1",
+            "1 set density 0",
+            "1 set wind 8",
+            "1 set stars 150",
+        ] {
+            assert!(Timeline::parse(line).is_ok(), "rejected `{line}`");
+        }
+    }
+}
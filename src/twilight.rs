@@ -0,0 +1,73 @@
+use nannou::prelude::*;
+
+use crate::lerp_rgb;
+
+/// Each phase of twilight covers this many degrees of solar depression.
+const PHASE_DEPTH: f32 = 6.;
+/// How far the sky is lifted from the night color towards the day color at
+/// the start of civil, nautical and astronomical twilight, and at full night.
+const SKY_LIFT: [f32; 4] = [0.3, 0.12, 0.04, 0.];
+/// Faintest star magnitude visible at the same points. Stars fade in over
+/// one magnitude as the limit passes them.
+const LIMITING_MAGNITUDE: [f32; 4] = [-1.5, 1.5, 4., 6.];
+
+/// The stages the sky passes through between sunset and full night, by how
+/// far the sun is below the horizon.
+#[derive(Clone, Copy, PartialEq)]
+pub enum Twilight {
+    Civil,
+    Nautical,
+    Astronomical,
+    Night,
+}
+
+impl Twilight {
+    pub fn from_depression(depression: f32) -> Self {
+        match (depression / PHASE_DEPTH) as u32 {
+            0 => Self::Civil,
+            1 => Self::Nautical,
+            2 => Self::Astronomical,
+            _ => Self::Night,
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Civil => "civil twilight",
+            Self::Nautical => "nautical twilight",
+            Self::Astronomical => "astronomical twilight",
+            Self::Night => "night",
+        }
+    }
+}
+
+/// Sky color with the sun `depression` degrees below the horizon, lifting
+/// the last of the sky gradient's `stops` towards the first.
+pub fn sky_color(stops: &[Srgb<u8>], depression: f32) -> Srgb<u8> {
+    let night = stops[stops.len() - 1];
+    lerp_rgb(night, stops[0], interpolate(&SKY_LIFT, depression))
+}
+
+/// How visible a star of `magnitude` is with the sun `depression` degrees
+/// below the horizon, so the brightest stars show in civil twilight and the
+/// faintest only once astronomical twilight is under way.
+pub fn star_visibility(depression: f32, magnitude: f32) -> f32 {
+    clamp(
+        interpolate(&LIMITING_MAGNITUDE, depression) - magnitude,
+        0.,
+        1.,
+    )
+}
+
+/// Piecewise-linear lookup into per-phase values.
+fn interpolate(stops: &[f32; 4], depression: f32) -> f32 {
+    let t = clamp(depression / PHASE_DEPTH, 0., 3.);
+    let i = (t as usize).min(2);
+    stops[i] + (stops[i + 1] - stops[i]) * (t - i as f32)
+}
+
+/// 0 at sunset, rising to 1 once astronomical twilight ends, for features
+/// that only show against a fully dark sky.
+pub fn darkness(depression: f32) -> f32 {
+    clamp(depression / (PHASE_DEPTH * 3.), 0., 1.)
+}